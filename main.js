@@ -315,6 +315,31 @@ async function loadTabData(tabName) {
   }
 }
 
+// 検索結果として表示する最大件数（超えた分は総件数のみ表示）
+const SEARCH_RESULT_LIMIT = 1000
+
+// 横断検索から指定した種類の結果だけを取得（ウィンドウ間で同じ並び順・一致判定を使う）
+// 一致した総件数も返す
+async function searchByKind(kind, query) {
+  const limitKeys = { history: 'history', bookmark: 'bookmarks', ip: 'ips' }
+  const totalKeys = { history: 'total_history', bookmark: 'total_bookmarks', ip: 'total_ips' }
+  const limits = { history: 0, bookmarks: 0, ips: 0, [limitKeys[kind]]: SEARCH_RESULT_LIMIT }
+  const response = await invoke('search_all', { query, limits })
+  return {
+    items: response.results.filter(result => result.kind === kind),
+    total: response[totalKeys[kind]]
+  }
+}
+
+// 検索結果が上限で切り詰められた場合に総件数を表示
+function showSearchTotal(listElement, shown, total) {
+  if (total <= shown) return
+  const note = document.createElement('div')
+  note.className = 'search-total-note'
+  note.textContent = `一致 ${total} 件のうち先頭の ${shown} 件を表示しています。条件を絞り込んでください`
+  listElement.appendChild(note)
+}
+
 // 全データ読み込み
 async function loadAllData() {
   await Promise.all([
@@ -328,15 +353,15 @@ async function loadAllData() {
 async function loadHistory(searchQuery = '', sortBy = '') {
   try {
     let history
+    let total = 0
     if (searchQuery) {
-      history = await invoke('search_clipboard_history', { query: searchQuery })
-    } else if (sortBy || elements.historySort.value !== 'recent') {
+      ({ items: history, total } = await searchByKind('history', searchQuery))
+    } else {
       const sortMethod = sortBy || elements.historySort.value
       history = await invoke('get_sorted_history', { sortBy: sortMethod })
-    } else {
-      history = await invoke('get_clipboard_history')
     }
     displayHistory(history)
+    showSearchTotal(elements.historyList, history.length, total)
   } catch (error) {
     console.error('履歴取得エラー:', error)
     elements.historyList.innerHTML = '<div class="error">履歴の取得に失敗しました</div>'
@@ -352,10 +377,8 @@ function displayHistory(history) {
     return
   }
   
-  // 新しい順にソート
-  const sortedHistory = history.sort((a, b) => new Date(b.timestamp) - new Date(a.timestamp))
-  
-  sortedHistory.forEach((item, index) => {
+  // 並び順はバックエンドで決定済み（ピン留めが先頭）
+  history.forEach((item, index) => {
     const card = createHistoryCard(item, index)
     elements.historyList.appendChild(card)
   })
@@ -398,15 +421,15 @@ function createHistoryCard(item, index) {
 async function loadBookmarks(searchQuery = '', sortBy = '') {
   try {
     let bookmarks
+    let total = 0
     if (searchQuery) {
-      bookmarks = await invoke('search_bookmarks', { query: searchQuery })
-    } else if (sortBy || elements.bookmarkSort.value !== 'recent') {
+      ({ items: bookmarks, total } = await searchByKind('bookmark', searchQuery))
+    } else {
       const sortMethod = sortBy || elements.bookmarkSort.value
       bookmarks = await invoke('get_sorted_bookmarks', { sortBy: sortMethod })
-    } else {
-      bookmarks = await invoke('get_bookmarks')
     }
     displayBookmarks(bookmarks)
+    showSearchTotal(elements.bookmarksList, bookmarks.length, total)
   } catch (error) {
     console.error('ブックマーク取得エラー:', error)
     elements.bookmarksList.innerHTML = '<div class="error">ブックマークの取得に失敗しました</div>'
@@ -468,12 +491,14 @@ function createBookmarkCard(bookmark) {
 async function loadIPs(searchQuery = '') {
   try {
     let ips
+    let total = 0
     if (searchQuery) {
-      ips = await invoke('search_ip_history', { query: searchQuery })
+      ({ items: ips, total } = await searchByKind('ip', searchQuery))
     } else {
       ips = await invoke('get_recent_ips')
     }
     displayIPs(ips)
+    showSearchTotal(elements.ipsList, ips.length, total)
   } catch (error) {
     console.error('IP履歴取得エラー:', error)
     elements.ipsList.innerHTML = '<div class="error">IP履歴の取得に失敗しました</div>'
@@ -64,7 +64,17 @@ function switchTab(tabName) {
 // データ読み込み
 async function loadData() {
   try {
-    appData = await invoke('get_app_data')
+    // 履歴・ブックマークは要約のみ取得し、本文は選択時に取得する
+    const [history, bookmarks, response] = await Promise.all([
+      invoke('list_history', { limit: 15, sortBy: 'frecency' }),
+      invoke('list_bookmarks', { limit: 10, sortBy: 'frecency' }),
+      invoke('search_all', { query: '', limits: { history: 0, bookmarks: 0, ips: 50 } })
+    ])
+    appData = {
+      history: history.items,
+      bookmarks: bookmarks.items,
+      recent_ips: response.results.filter(result => result.kind === 'ip')
+    }
     console.log('データ読み込み完了:', appData)
     displayCurrentTab()
   } catch (error) {
@@ -95,10 +105,8 @@ function displayHistory() {
     return
   }
 
-  // 最新順にソート（最大15件）
+  // 並び順と件数はlist_historyで決定済み（ピン留め優先、利用頻度と新しさ順）
   const recentItems = appData.history
-    .sort((a, b) => new Date(b.timestamp) - new Date(a.timestamp))
-    .slice(0, 15)
 
   if (recentItems.length === 0) {
     showEmptyState(elements.historyList, '📋', '履歴がありません')
@@ -106,15 +114,15 @@ function displayHistory() {
   }
 
   const html = recentItems.map(item => {
-    const preview = item.content.length > 50 
-      ? item.content.substring(0, 50) + '...' 
-      : item.content
+    const preview = item.preview.length > 50 || item.truncated
+      ? item.preview.substring(0, 50) + '...' 
+      : item.preview
     
     const timeAgo = getTimeAgo(item.timestamp)
-    const size = formatFileSize(item.size || item.content.length)
+    const size = formatFileSize(item.size)
     
     return `
-      <div class="small-item" onclick="copyAndPaste('${escapeHtml(item.content)}')" title="${escapeHtml(item.content)}">
+      <div class="small-item" onclick="copyAndPasteItem('history', '${item.id}')" title="${escapeHtml(item.preview)}">
         <span class="small-item-icon">📄</span>
         <div class="small-item-content">
           <div class="small-item-text">${escapeHtml(preview)}</div>
@@ -135,10 +143,7 @@ function displayBookmarks() {
     return
   }
 
-  // 使用頻度順にソート（最大10件）
   const topBookmarks = appData.bookmarks
-    .sort((a, b) => (b.access_count || 0) - (a.access_count || 0))
-    .slice(0, 10)
 
   if (topBookmarks.length === 0) {
     showEmptyState(elements.bookmarksList, '⭐', 'ブックマークがありません')
@@ -146,17 +151,17 @@ function displayBookmarks() {
   }
 
   const html = topBookmarks.map(bookmark => {
-    const preview = bookmark.content.length > 40 
-      ? bookmark.content.substring(0, 40) + '...' 
-      : bookmark.content
+    const preview = bookmark.preview.length > 40 || bookmark.truncated
+      ? bookmark.preview.substring(0, 40) + '...' 
+      : bookmark.preview
     
     const accessCount = bookmark.access_count || 0
     
     return `
-      <div class="small-item" onclick="copyAndPaste('${escapeHtml(bookmark.content)}')" title="${escapeHtml(bookmark.content)}">
+      <div class="small-item" onclick="copyAndPasteItem('bookmark', '${bookmark.id}')" title="${escapeHtml(bookmark.preview)}">
         <span class="small-item-icon">⭐</span>
         <div class="small-item-content">
-          <div class="small-item-text">${escapeHtml(bookmark.name)}</div>
+          <div class="small-item-text">${escapeHtml(bookmark.title)}</div>
           <div class="small-item-meta">${escapeHtml(preview)}</div>
         </div>
         <span class="small-item-size">${accessCount}回</span>
@@ -174,9 +179,7 @@ function displayIPs() {
     return
   }
 
-  // 使用回数順にソート
   const sortedIPs = appData.recent_ips
-    .sort((a, b) => (b.count || 0) - (a.count || 0))
 
   if (sortedIPs.length === 0) {
     showEmptyState(elements.ipsList, '🌐', 'IP履歴がありません')
@@ -202,8 +205,27 @@ function displayIPs() {
   elements.ipsList.innerHTML = html
 }
 
-// コピー＆貼り付け
-async function copyAndPaste(content) {
+// paste_content に渡すアイテムの種類（kind: "history" | "bookmark"）
+const PASTE_ITEM_TYPES = { history: 'clipboard', bookmark: 'bookmark' }
+
+// 本文を分割して取得してからコピー＆貼り付け（貼り付けを元のアイテムの利用として記録する）
+async function copyAndPasteItem(kind, itemId) {
+  try {
+    let content = ''
+    let offset = 0
+    while (offset !== null) {
+      const chunk = await invoke('get_item_content', { kind, itemId, offset })
+      content += chunk.data
+      offset = chunk.next_offset ?? null
+    }
+    await copyAndPaste(content, itemId, PASTE_ITEM_TYPES[kind])
+  } catch (error) {
+    console.error('本文取得エラー:', error)
+  }
+}
+
+// コピー＆貼り付け（itemId / itemType を省略した場合は内容が一致するアイテムに記録）
+async function copyAndPaste(content, itemId = null, itemType = null) {
   try {
     console.log('コピー＆貼り付け:', content.substring(0, 50))
     
@@ -216,7 +238,7 @@ async function copyAndPaste(content) {
     // 少し待ってから貼り付け（ウィンドウが閉じるのを待つ）
     setTimeout(async () => {
       try {
-        await invoke('paste_content', { content })
+        await invoke('paste_content', { content, itemId, itemType })
         console.log('貼り付け処理完了')
       } catch (error) {
         console.error('貼り付けエラー:', error)
@@ -367,4 +389,5 @@ window.notifyWindowShown = notifyWindowShown;
 
 // グローバル関数として公開
 window.copyAndPaste = copyAndPaste
+window.copyAndPasteItem = copyAndPasteItem
 window.closeWindow = closeWindow
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+use crate::models::{AppData, BookmarkFolder};
+
+// フォルダ削除時の中身（子フォルダ・ブックマーク）の扱い
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeleteMode {
+    Cascade,  // 子フォルダとブックマークもまとめて削除
+    Reparent, // 削除するフォルダの親へ移動
+}
+
+impl DeleteMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "cascade" => Ok(Self::Cascade),
+            "reparent" => Ok(Self::Reparent),
+            _ => Err(format!("Unknown delete mode: {}", value)),
+        }
+    }
+}
+
+pub struct FolderTree;
+
+impl FolderTree {
+    pub fn find<'a>(folders: &'a [BookmarkFolder], folder_id: &str) -> Option<&'a BookmarkFolder> {
+        folders.iter().find(|folder| folder.id == folder_id)
+    }
+
+    // フォルダが存在するか（None はルート）
+    pub fn ensure_exists(folders: &[BookmarkFolder], folder_id: Option<&str>) -> Result<(), String> {
+        match folder_id {
+            Some(folder_id) if Self::find(folders, folder_id).is_none() => Err("Folder not found".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    // 同じ親フォルダの中で名前が重複しないか（exclude_id は名前変更・移動するフォルダ自身）
+    pub fn ensure_unique_name(
+        folders: &[BookmarkFolder],
+        parent_id: Option<&str>,
+        name: &str,
+        exclude_id: Option<&str>,
+    ) -> Result<(), String> {
+        let duplicate = folders.iter().any(|folder| {
+            folder.parent_id.as_deref() == parent_id && folder.name == name && Some(folder.id.as_str()) != exclude_id
+        });
+        if duplicate {
+            return Err(format!("A folder named '{}' already exists here", name));
+        }
+        Ok(())
+    }
+
+    // 移動先が自身または子孫フォルダでないか
+    pub fn ensure_movable(folders: &[BookmarkFolder], folder_id: &str, new_parent_id: Option<&str>) -> Result<(), String> {
+        if new_parent_id.is_some_and(|parent_id| Self::subtree(folders, folder_id).contains(parent_id)) {
+            return Err("Cannot move a folder into itself or one of its subfolders".to_string());
+        }
+        Ok(())
+    }
+
+    // 指定フォルダとその下のすべてのフォルダのID
+    pub fn subtree(folders: &[BookmarkFolder], folder_id: &str) -> HashSet<String> {
+        let mut ids = HashSet::from([folder_id.to_string()]);
+        let mut pending = vec![folder_id.to_string()];
+
+        while let Some(parent_id) = pending.pop() {
+            for folder in folders.iter().filter(|folder| folder.parent_id.as_deref() == Some(parent_id.as_str())) {
+                if ids.insert(folder.id.clone()) {
+                    pending.push(folder.id.clone());
+                }
+            }
+        }
+
+        ids
+    }
+
+    // ルートから指定フォルダまでのフォルダ（指定フォルダを含む）
+    pub fn path(folders: &[BookmarkFolder], folder_id: &str) -> Vec<BookmarkFolder> {
+        let mut path = Vec::new();
+        let mut current = Self::find(folders, folder_id);
+
+        while let Some(folder) = current {
+            if path.iter().any(|visited: &BookmarkFolder| visited.id == folder.id) {
+                break; // 循環（通常は発生しない）
+            }
+            path.push(folder.clone());
+            current = folder.parent_id.as_deref().and_then(|parent_id| Self::find(folders, parent_id));
+        }
+
+        path.reverse();
+        path
+    }
+
+    // フォルダを削除し、削除したフォルダ数とブックマーク数を返す
+    pub fn delete(data: &mut AppData, folder_id: &str, mode: DeleteMode) -> Result<(usize, usize), String> {
+        let folder = Self::find(&data.bookmark_folders, folder_id).ok_or("Folder not found")?.clone();
+
+        match mode {
+            DeleteMode::Cascade => {
+                let ids = Self::subtree(&data.bookmark_folders, folder_id);
+                let original_bookmarks = data.bookmarks.len();
+                data.bookmark_folders.retain(|folder| !ids.contains(&folder.id));
+                data.bookmarks
+                    .retain(|bookmark| !bookmark.folder_id.as_ref().is_some_and(|id| ids.contains(id)));
+                Ok((ids.len(), original_bookmarks - data.bookmarks.len()))
+            }
+            DeleteMode::Reparent => {
+                let parent_id = folder.parent_id.clone();
+                // 移動先に同名のフォルダがある場合は削除しない（削除するフォルダ自身は除く）
+                let siblings: HashSet<&str> = data
+                    .bookmark_folders
+                    .iter()
+                    .filter(|sibling| sibling.parent_id == parent_id && sibling.id != folder_id)
+                    .map(|sibling| sibling.name.as_str())
+                    .collect();
+                if let Some(child) = data
+                    .bookmark_folders
+                    .iter()
+                    .find(|child| child.parent_id.as_deref() == Some(folder_id) && siblings.contains(child.name.as_str()))
+                {
+                    return Err(format!("A folder named '{}' already exists in the parent folder", child.name));
+                }
+
+                for child in data.bookmark_folders.iter_mut().filter(|child| child.parent_id.as_deref() == Some(folder_id)) {
+                    child.parent_id = parent_id.clone();
+                }
+                for bookmark in data.bookmarks.iter_mut().filter(|bookmark| bookmark.folder_id.as_deref() == Some(folder_id)) {
+                    bookmark.folder_id = parent_id.clone();
+                }
+                data.bookmark_folders.retain(|folder| folder.id != folder_id);
+                Ok((1, 0))
+            }
+        }
+    }
+
+    // 読み込み時の整合性修復: 存在しないフォルダを参照するブックマーク・フォルダと
+    // 親をたどると循環するフォルダをルートへ移し、修復した件数を返す
+    pub fn repair(data: &mut AppData) -> usize {
+        let known: HashSet<String> = data.bookmark_folders.iter().map(|folder| folder.id.clone()).collect();
+        let mut repaired = 0;
+
+        for bookmark in &mut data.bookmarks {
+            if bookmark.folder_id.as_ref().is_some_and(|id| !known.contains(id)) {
+                bookmark.folder_id = None;
+                repaired += 1;
+            }
+        }
+        for folder in &mut data.bookmark_folders {
+            if folder.parent_id.as_ref().is_some_and(|id| !known.contains(id)) {
+                folder.parent_id = None;
+                repaired += 1;
+            }
+        }
+
+        for index in 0..data.bookmark_folders.len() {
+            let folder_id = data.bookmark_folders[index].id.clone();
+            let mut visited = HashSet::new();
+            let mut current = data.bookmark_folders[index].parent_id.clone();
+
+            while let Some(parent_id) = current {
+                if parent_id == folder_id {
+                    data.bookmark_folders[index].parent_id = None;
+                    repaired += 1;
+                    break;
+                }
+                if !visited.insert(parent_id.clone()) {
+                    break; // 自身を含まない循環（そのフォルダの処理時に修復する）
+                }
+                current = Self::find(&data.bookmark_folders, &parent_id).and_then(|parent| parent.parent_id.clone());
+            }
+        }
+
+        repaired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkItem;
+
+    // ルート ─ work ─ projects ─ archive と、work・projects に1件ずつのブックマーク
+    fn sample_data() -> (AppData, [String; 3]) {
+        let mut data = AppData::default();
+        let work = BookmarkFolder::new("work".to_string(), None);
+        let projects = BookmarkFolder::new("projects".to_string(), Some(work.id.clone()));
+        let archive = BookmarkFolder::new("archive".to_string(), Some(projects.id.clone()));
+        let ids = [work.id.clone(), projects.id.clone(), archive.id.clone()];
+        data.bookmark_folders = vec![work, projects, archive];
+
+        for (name, folder_id) in [("wiki", &ids[0]), ("repo", &ids[1])] {
+            let mut bookmark = BookmarkItem::new(name.to_string(), name.to_string(), "text".to_string(), Vec::new());
+            bookmark.folder_id = Some(folder_id.clone());
+            data.bookmarks.push(bookmark);
+        }
+        (data, ids)
+    }
+
+    #[test]
+    fn cascade_deletes_the_subtree_and_its_bookmarks() {
+        let (mut data, [work, ..]) = sample_data();
+
+        assert_eq!(FolderTree::delete(&mut data, &work, DeleteMode::Cascade), Ok((3, 2)));
+        assert!(data.bookmark_folders.is_empty());
+        assert!(data.bookmarks.is_empty());
+    }
+
+    #[test]
+    fn reparent_moves_children_and_bookmarks_to_the_parent() {
+        let (mut data, [work, projects, archive]) = sample_data();
+
+        assert_eq!(FolderTree::delete(&mut data, &projects, DeleteMode::Reparent), Ok((1, 0)));
+        assert_eq!(data.bookmark_folders.len(), 2);
+        assert_eq!(FolderTree::find(&data.bookmark_folders, &archive).unwrap().parent_id.as_deref(), Some(work.as_str()));
+        assert!(data.bookmarks.iter().all(|bookmark| bookmark.folder_id.as_deref() == Some(work.as_str())));
+
+        // 移動先に同名のフォルダがある場合は削除しない
+        data.bookmark_folders.push(BookmarkFolder::new("archive".to_string(), None));
+        assert!(FolderTree::delete(&mut data, &work, DeleteMode::Reparent).is_err());
+        assert_eq!(data.bookmark_folders.len(), 3);
+    }
+
+    #[test]
+    fn folders_cannot_move_into_themselves_or_descendants() {
+        let (data, [work, projects, archive]) = sample_data();
+        let folders = &data.bookmark_folders;
+
+        assert!(FolderTree::ensure_movable(folders, &work, Some(&work)).is_err());
+        assert!(FolderTree::ensure_movable(folders, &work, Some(&archive)).is_err());
+        assert!(FolderTree::ensure_movable(folders, &archive, Some(&work)).is_ok());
+        assert!(FolderTree::ensure_movable(folders, &projects, None).is_ok());
+    }
+
+    #[test]
+    fn repair_breaks_cycles_and_dangling_references() {
+        let (mut data, [work, projects, archive]) = sample_data();
+        // work → archive → projects → work の循環と、存在しないフォルダへの参照
+        data.bookmark_folders[0].parent_id = Some(archive.clone());
+        data.bookmarks[0].folder_id = Some("missing".to_string());
+
+        assert_eq!(FolderTree::repair(&mut data), 2);
+        assert_eq!(data.bookmarks[0].folder_id, None);
+        assert_eq!(FolderTree::find(&data.bookmark_folders, &work).unwrap().parent_id, None);
+        assert_eq!(FolderTree::path(&data.bookmark_folders, &archive).len(), 3);
+        assert_eq!(FolderTree::path(&data.bookmark_folders, &projects)[0].id, work);
+        assert_eq!(FolderTree::repair(&mut data), 0);
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex, OnceLock};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::models::{CaptureRule, RuleAction, RuleConditions};
+
+// ルール評価の入力
+#[derive(Debug, Clone)]
+pub struct CaptureInput<'a> {
+    pub text: &'a str,
+    pub content_type: &'a str,
+    pub source_app: Option<&'a str>,
+}
+
+// ルール評価の結果（キャプチャ処理とtest_capture_rulesで共通）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleOutcome {
+    pub ignored: bool,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub bookmark_sets: Vec<String>,
+    pub skip_ip_detection: bool,
+    pub matched_rules: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// コンパイル済みのルール（正規表現のコンパイルに失敗した場合はエラーを保持する）
+struct CompiledRule {
+    rule: CaptureRule,
+    pattern: Option<Result<Regex, String>>,
+    rewrites: Vec<Option<Result<Regex, String>>>, // actions と同じ順序（Rewrite 以外は None）
+}
+
+// 直近に評価したルール一式のコンパイル結果（キーはルールのJSON表現）
+// 設定が変わるまでキャプチャのたびに正規表現をコンパイルし直さない
+type CompiledCache = Option<(String, Arc<Vec<CompiledRule>>)>;
+static COMPILED_RULES: OnceLock<Mutex<CompiledCache>> = OnceLock::new();
+
+pub struct RuleEngine;
+
+impl RuleEngine {
+    // ルールを順番に評価する。書き換えは後続ルールの入力になる
+    pub fn evaluate(rules: &[CaptureRule], input: &CaptureInput) -> RuleOutcome {
+        let compiled = Self::compiled(rules);
+        let mut outcome = RuleOutcome {
+            text: input.text.to_string(),
+            ..Default::default()
+        };
+
+        for compiled_rule in compiled.iter().filter(|compiled_rule| compiled_rule.rule.enabled) {
+            let rule = &compiled_rule.rule;
+            let current = CaptureInput { text: &outcome.text, ..input.clone() };
+            match Self::matches(compiled_rule, &current) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    outcome.errors.push(format!("{}: {}", rule.name, e));
+                    continue;
+                }
+            }
+
+            outcome.matched_rules.push(rule.name.clone());
+
+            for (action, rewrite) in rule.actions.iter().zip(&compiled_rule.rewrites) {
+                match action {
+                    RuleAction::Ignore => {
+                        outcome.ignored = true;
+                        return outcome;
+                    }
+                    RuleAction::StripWhitespace => {
+                        outcome.text = outcome.text.trim().to_string();
+                    }
+                    RuleAction::Rewrite { replacement, .. } => match rewrite {
+                        Some(Ok(regex)) => {
+                            outcome.text = regex.replace_all(&outcome.text, replacement.as_str()).into_owned();
+                        }
+                        Some(Err(e)) => outcome.errors.push(format!("{}: {}", rule.name, e)),
+                        None => {}
+                    },
+                    RuleAction::AddTags { tags } => {
+                        for tag in tags {
+                            if !outcome.tags.contains(tag) {
+                                outcome.tags.push(tag.clone());
+                            }
+                        }
+                    }
+                    RuleAction::AutoBookmark { set } => {
+                        if !outcome.bookmark_sets.contains(set) {
+                            outcome.bookmark_sets.push(set.clone());
+                        }
+                    }
+                    RuleAction::SkipIpDetection => {
+                        outcome.skip_ip_detection = true;
+                    }
+                }
+            }
+
+            if rule.stop_processing {
+                break;
+            }
+        }
+
+        // 書き換えの結果が空になった場合は取り込まない
+        if outcome.text.trim().is_empty() {
+            outcome.ignored = true;
+        }
+
+        outcome
+    }
+
+    // ルール一式をコンパイルする（前回と同じルールならキャッシュを再利用）
+    fn compiled(rules: &[CaptureRule]) -> Arc<Vec<CompiledRule>> {
+        let key = serde_json::to_string(rules).unwrap_or_default();
+        let cache = COMPILED_RULES.get_or_init(|| Mutex::new(None));
+
+        if let Ok(cache) = cache.lock() {
+            if let Some((cached_key, compiled)) = cache.as_ref() {
+                if *cached_key == key {
+                    return Arc::clone(compiled);
+                }
+            }
+        }
+
+        let compiled = Arc::new(rules.iter().map(Self::compile).collect::<Vec<_>>());
+        if let Ok(mut cache) = cache.lock() {
+            *cache = Some((key, Arc::clone(&compiled)));
+        }
+        compiled
+    }
+
+    fn compile(rule: &CaptureRule) -> CompiledRule {
+        let pattern = rule
+            .conditions
+            .pattern
+            .as_ref()
+            .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e)));
+        let rewrites = rule
+            .actions
+            .iter()
+            .map(|action| match action {
+                RuleAction::Rewrite { pattern, .. } => Some(Regex::new(pattern).map_err(|e| e.to_string())),
+                _ => None,
+            })
+            .collect();
+
+        CompiledRule { rule: rule.clone(), pattern, rewrites }
+    }
+
+    // 条件はすべて満たす必要がある（未指定の条件は無視）
+    fn matches(compiled_rule: &CompiledRule, input: &CaptureInput) -> Result<bool, String> {
+        let conditions: &RuleConditions = &compiled_rule.rule.conditions;
+        let length = input.text.chars().count();
+        if conditions.min_length.is_some_and(|min| length < min) {
+            return Ok(false);
+        }
+        if conditions.max_length.is_some_and(|max| length > max) {
+            return Ok(false);
+        }
+
+        if let Some(content_type) = &conditions.content_type {
+            if !content_type.eq_ignore_ascii_case(input.content_type) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(source_app) = &conditions.source_app {
+            let matched = input
+                .source_app
+                .is_some_and(|app| app.to_lowercase().contains(&source_app.to_lowercase()));
+            if !matched {
+                return Ok(false);
+            }
+        }
+
+        if let Some(pattern) = &compiled_rule.pattern {
+            let regex = pattern.as_ref().map_err(|e| e.clone())?;
+            if !regex.is_match(input.text) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    // 設定保存前の検証（不正な正規表現を拒否）
+    pub fn validate(rules: &[CaptureRule]) -> Result<(), String> {
+        for rule in rules {
+            if let Some(pattern) = &rule.conditions.pattern {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Rule '{}' has an invalid pattern: {}", rule.name, e))?;
+            }
+            for action in &rule.actions {
+                if let RuleAction::Rewrite { pattern, .. } = action {
+                    Regex::new(pattern)
+                        .map_err(|e| format!("Rule '{}' has an invalid rewrite pattern: {}", rule.name, e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,29 +1,101 @@
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use std::fs;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use tauri::{AppHandle, Emitter};
-use chrono::Utc;
-use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use clipboard::{ClipboardProvider, ClipboardContext};
 use regex::Regex;
 use serde_json;
-use crate::models::{AppData, ClipboardItem};
+use crate::models::{AppData, BookmarkItem, CaptureState, ClipboardItem, MonitorStatus, RetentionPreview, RetentionSettings};
+use crate::capture_rules::{CaptureInput, RuleEngine};
+use crate::content_classifier::ContentClassifier;
+use crate::eviction::Eviction;
 use crate::file_manager::FileManager;
+use crate::retention::Retention;
+use crate::search_index::SearchIndex;
+use crate::sensitive_detector::SensitiveDetector;
+use crate::session_recorder::SessionRecorder;
+use crate::tray_manager::TrayManager;
 
 pub struct ClipboardMonitor {
     app_data: Arc<Mutex<AppData>>,
     last_clipboard_content: Arc<Mutex<Option<String>>>,
     is_monitoring: Arc<Mutex<bool>>,
+    generation: Arc<AtomicU64>,
+    status: Arc<Mutex<MonitorStatus>>,
+    self_writes: Arc<Mutex<Vec<SelfWrite>>>,
+    recorder: SessionRecorder,
+    search_index: Arc<Mutex<SearchIndex>>,
 }
 
+// アプリ自身が書き込んだクリップボード内容（監視で新規取り込みしないための目印）
+struct SelfWrite {
+    hash: u64,
+    item_id: Option<String>,
+    item_type: Option<String>,
+    written_at: Instant,
+}
+
+// 監視タスク間で共有する状態
+#[derive(Clone)]
+struct MonitorShared {
+    app_data: Arc<Mutex<AppData>>,
+    last_content: Arc<Mutex<Option<String>>>,
+    monitoring_flag: Arc<Mutex<bool>>,
+    generation_counter: Arc<AtomicU64>,
+    status: Arc<Mutex<MonitorStatus>>,
+    self_writes: Arc<Mutex<Vec<SelfWrite>>>,
+    recorder: SessionRecorder,
+    search_index: Arc<Mutex<SearchIndex>>,
+}
+
+// 取り込みパイプラインへの入力（OSから取得する情報は呼び出し側で用意する）
+pub struct CaptureRequest<'a> {
+    pub text: String,
+    pub selection: &'a str,
+    pub source_app: Option<String>,
+    pub concealed: bool,
+    pub now: DateTime<Utc>,
+}
+
+// 変更を検出した時点でOSから取得した情報（記録と取り込みで共有する）
+#[derive(Debug, Clone, Default)]
+pub struct SourceContext {
+    pub source_app: Option<String>,
+    pub concealed: bool,
+}
+
+// 取り込み結果（イベント通知やクリップボード消去は呼び出し側で行う）
+#[derive(Debug, Default)]
+pub struct CaptureResult {
+    pub captured: bool,
+    pub notify_content: Option<String>, // マスク済みの内容
+    pub sensitive_kind: Option<&'static str>,
+    pub clear_clipboard_after_secs: u64,
+    pub added_ips: Vec<String>,
+    pub item_id: Option<String>,       // 追加・更新した履歴アイテム
+    pub bookmark_ids: Vec<String>,     // 自動ブックマークで追加したブックマーク
+}
+
+// 再起動バックオフの上限（秒）
+const MONITOR_BACKOFF_MAX_SECS: u64 = 60;
+// 自己書き込みの目印を保持する時間（秒）
+const SELF_WRITE_TTL_SECS: u64 = 10;
+
 impl ClipboardMonitor {
-    pub fn new(app_data: Arc<Mutex<AppData>>) -> Self {
+    pub fn new(app_data: Arc<Mutex<AppData>>, search_index: Arc<Mutex<SearchIndex>>) -> Self {
         Self {
             app_data,
             last_clipboard_content: Arc::new(Mutex::new(None)),
             is_monitoring: Arc::new(Mutex::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            status: Arc::new(Mutex::new(MonitorStatus::default())),
+            self_writes: Arc::new(Mutex::new(Vec::new())),
+            recorder: SessionRecorder::default(),
+            search_index,
         }
     }
 
@@ -34,25 +106,19 @@ impl ClipboardMonitor {
         tokio::spawn(async move {
             // メモリ最適化: 自動保存間隔を動的に調整
             let mut interval = tokio::time::interval(Duration::from_secs(60)); // 初期は60秒
-            let mut last_data_hash: Option<u64> = None;
+            let mut last_saved_revision: Option<u64> = None;
             
             loop {
                 interval.tick().await;
                 
                 if let Ok(data) = app_data.lock() {
-                    // メモリ最適化: データのハッシュ値をチェックして変更がある場合のみ保存
-                    let mut hasher = DefaultHasher::new();
-                    data.history.len().hash(&mut hasher);
-                    data.bookmarks.len().hash(&mut hasher);
-                    data.recent_ips.len().hash(&mut hasher);
-                    let current_hash = hasher.finish();
-                    
-                    if last_data_hash == Some(current_hash) {
-                        // データに変更がない場合はスキップ
+                    // 前回の保存以降にデータが変更された場合のみ保存
+                    let current_revision = data.revision;
+                    if last_saved_revision == Some(current_revision) {
                         continue;
                     }
                     
-                    last_data_hash = Some(current_hash);
+                    last_saved_revision = Some(current_revision);
                     let data_clone = data.clone();
                     drop(data); // Mutexのロックを解放
                     
@@ -64,12 +130,12 @@ impl ClipboardMonitor {
                         }
                     };
                     
-                    // メモリ効率的なシリアライゼーション
-                    if let Ok(json_content) = serde_json::to_string(&data_clone) {
+                    // メモリ効率的なシリアライゼーション（機密アイテムは除外）
+                    if let Ok(json_content) = serde_json::to_string(&data_clone.for_persistence()) {
                         if let Err(e) = fs::write(&file_path, json_content) {
                             log::warn!("自動保存エラー: {}", e);
                         } else {
-                            log::debug!("自動保存完了: {:?} (hash: {})", file_path, current_hash);
+                            log::debug!("自動保存完了: {:?} (revision: {})", file_path, current_revision);
                         }
                     }
                 }
@@ -86,131 +152,795 @@ impl ClipboardMonitor {
         
         *is_monitoring = true;
         
-        let app_data = Arc::clone(&self.app_data);
-        let last_content = Arc::clone(&self.last_clipboard_content);
-        let monitoring_flag = Arc::clone(&self.is_monitoring);
+        // 停止直後の再開で旧ループが残らないよう世代番号で識別
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        
+        let shared = MonitorShared {
+            app_data: Arc::clone(&self.app_data),
+            last_content: Arc::clone(&self.last_clipboard_content),
+            monitoring_flag: Arc::clone(&self.is_monitoring),
+            generation_counter: Arc::clone(&self.generation),
+            status: Arc::clone(&self.status),
+            self_writes: Arc::clone(&self.self_writes),
+            recorder: self.recorder.clone(),
+            search_index: Arc::clone(&self.search_index),
+        };
+        
+        // PRIMARY選択の監視（Linux専用、設定で有効な場合のみ取り込む）
+        #[cfg(target_os = "linux")]
+        Self::spawn_primary_selection_loop(shared.clone(), generation, app_handle.clone());
         
+        // スーパーバイザー: 監視ループが異常終了した場合は指数バックオフで再起動
         tokio::spawn(async move {
-            // パフォーマンス最適化: アダプティブな監視間隔
-            let mut interval = tokio::time::interval(Duration::from_millis(250)); // より高速な応答
-            let mut consecutive_errors = 0;
-            let mut last_clipboard_hash: Option<u64> = None;
+            let status = &shared.status;
+            let mut attempt: u32 = 0;
             
             loop {
-                interval.tick().await;
+                let run_started = Instant::now();
+                Self::set_status(status, &app_handle, |status| {
+                    status.state = "running".to_string();
+                    status.started_at = Some(Utc::now());
+                    status.consecutive_errors = 0;
+                });
                 
-                // 監視停止チェック
-                if let Ok(is_running) = monitoring_flag.lock() {
-                    if !*is_running {
-                        break;
-                    }
+                let result = Self::run_monitor_loop(&shared, generation, &app_handle).await;
+                
+                let error = match result {
+                    Ok(()) => break,
+                    Err(error) => error,
+                };
+                
+                // 一定時間正常に動作していた場合はバックオフをリセット
+                if run_started.elapsed() > Duration::from_secs(MONITOR_BACKOFF_MAX_SECS) {
+                    attempt = 0;
                 }
+                let backoff_secs = (1u64 << attempt.min(6)).min(MONITOR_BACKOFF_MAX_SECS);
+                attempt += 1;
+                
+                log::error!("クリップボード監視が停止しました: {}。{}秒後に再起動します", error, backoff_secs);
+                Self::set_status(status, &app_handle, |status| {
+                    status.state = "restarting".to_string();
+                    status.restart_count += 1;
+                    status.last_error = Some(error.clone());
+                    status.last_error_at = Some(Utc::now());
+                });
                 
-                // クリップボード内容を取得（エラーハンドリング改善）
-                match ClipboardContext::new() {
-                    Ok(mut ctx) => {
-                        match ctx.get_contents() {
-                            Ok(text) => {
+                tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                
+                if !Self::is_current_run(&shared, generation) {
+                    break;
+                }
+            }
+            
+            // 新しい世代が起動済みの場合は状態を上書きしない
+            if shared.generation_counter.load(Ordering::SeqCst) == generation {
+                Self::set_status(status, &app_handle, |status| {
+                    status.state = "stopped".to_string();
+                });
+            }
+        });
+        
+        Ok(())
+    }
+
+    // X11 PRIMARY選択（選択しただけのテキスト）を監視する
+    // 選択中は内容が変わり続けるため、一定時間変化が無くなってから取り込む
+    #[cfg(target_os = "linux")]
+    fn spawn_primary_selection_loop(shared: MonitorShared, generation: u64, app_handle: AppHandle) {
+        use clipboard::x11_clipboard::{Primary, X11ClipboardContext};
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(250));
+            let mut last_primary: Option<String> = None;
+            let mut pending: Option<(String, Instant)> = None;
+
+            loop {
+                interval.tick().await;
+
+                if !Self::is_current_run(&shared, generation) {
+                    break;
+                }
+
+                let settings = match shared.app_data.lock() {
+                    Ok(data) => data.settings.primary_selection.clone(),
+                    Err(_) => continue,
+                };
+                if !settings.enabled {
+                    pending = None;
+                    continue;
+                }
+
+                // 選択が無い場合などは読み込みに失敗するため無視
+                let text = match X11ClipboardContext::<Primary>::new().and_then(|mut ctx| ctx.get_contents()) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+
+                if last_primary.as_ref() != Some(&text) {
+                    last_primary = Some(text.clone());
+                    pending = Some((text, Instant::now()));
+                    continue;
+                }
+
+                let debounce = Duration::from_millis(settings.debounce_ms);
+                if !pending.as_ref().is_some_and(|(_, changed_at)| changed_at.elapsed() >= debounce) {
+                    continue;
+                }
+                let Some((text, _)) = pending.take() else {
+                    continue;
+                };
+
+                if text.trim().chars().count() < settings.min_length {
+                    continue;
+                }
+
+                // CLIPBOARDにも同じ内容がある場合は通常の取り込みに任せる
+                let in_clipboard = shared
+                    .last_content
+                    .lock()
+                    .map(|last| last.as_deref() == Some(text.as_str()))
+                    .unwrap_or(false);
+                if in_clipboard {
+                    continue;
+                }
+
+                let context = Self::query_source_context(&shared).await;
+                let mut hasher = DefaultHasher::new();
+                text.hash(&mut hasher);
+                let capture_state = Self::current_capture_state(&shared.app_data);
+                shared.recorder.record(&text, hasher.finish(), "primary", false, &context, &capture_state);
+
+                Self::handle_capture(&shared, &app_handle, text, "primary", context);
+            }
+        });
+    }
+
+    fn is_current_run(shared: &MonitorShared, generation: u64) -> bool {
+        let is_running = shared.monitoring_flag.lock().map(|flag| *flag).unwrap_or(false);
+        is_running && shared.generation_counter.load(Ordering::SeqCst) == generation
+    }
+
+    // 監視ループ本体（停止要求でOk、致命的エラーでErrを返す）
+    async fn run_monitor_loop(shared: &MonitorShared, generation: u64, app_handle: &AppHandle) -> Result<(), String> {
+        let (app_data, status) = (&shared.app_data, &shared.status);
+        // パフォーマンス最適化: アダプティブな監視間隔
+        let mut interval = tokio::time::interval(Duration::from_millis(250)); // より高速な応答
+        let mut consecutive_errors = 0;
+        let mut last_clipboard_hash: Option<u64> = None;
+        // 短時間に連続した変更は最後の内容だけを取り込む（内容, 取得元の情報, 検出時刻, 待機時間）
+        let mut pending: Option<(String, SourceContext, Instant, Duration)> = None;
+        
+        loop {
+            interval.tick().await;
+            
+            // 監視停止チェック
+            if !Self::is_current_run(shared, generation) {
+                return Ok(());
+            }
+
+            // 時間指定の一時停止の期限切れチェック
+            Self::check_pause_expiry(app_data, app_handle);
+            
+            // クリップボード内容を取得（エラーハンドリング改善）
+            let mut changed: Option<(String, u64)> = None;
+            match ClipboardContext::new() {
+                Ok(mut ctx) => {
+                    match ctx.get_contents() {
+                        Ok(text) => {
+                            if consecutive_errors > 0 {
                                 consecutive_errors = 0; // エラーカウントリセット
-                                
-                                // パフォーマンス最適化: ハッシュベースの変更検出
-                                let mut hasher = DefaultHasher::new();
-                                text.hash(&mut hasher);
-                                let current_hash = hasher.finish();
-                                
-                                if last_clipboard_hash != Some(current_hash) && !text.trim().is_empty() {
-                                    last_clipboard_hash = Some(current_hash);
-                                
-                                    // 前回の内容と比較
-                                    if let Ok(mut last) = last_content.lock() {
-                                        if last.as_ref() != Some(&text) {
-                                            *last = Some(text.clone());
-                                            
-                                            // 履歴に追加
-                                            if let Ok(mut data) = app_data.lock() {
-                                                // 完全重複アイテムを検索・削除
-                                                let mut removed_count = 0;
-                                                data.history.retain(|item| {
-                                                    if item.content == text {
-                                                        removed_count += 1;
-                                                        false // 削除
-                                                    } else {
-                                                        true // 保持
-                                                    }
-                                                });
-                                                
-                                                if removed_count > 0 {
-                                                    log::info!("重複アイテム{}件を自動削除しました", removed_count);
-                                                }
-                                                
-                                                // 新しいアイテムを追加
-                                                let item = ClipboardItem {
-                                                    id: Uuid::new_v4().to_string(),
-                                                    content: text.clone(),
-                                                    content_type: "text".to_string(),
-                                                    timestamp: Utc::now(),
-                                                    size: text.len(),
-                                                    access_count: 0,
-                                                    last_accessed: None,
-                                                };
-                                                
-                                                // 設定で指定された件数制限
-                                                let limit = data.settings.history_limit;
-                                                if data.history.len() >= limit {
-                                                    data.history.remove(0);
-                                                }
-                                                
-                                                data.history.push(item);
-                                                log::info!("クリップボード変更検出: {} chars", text.len());
-                                                
-                                                // フロントエンドに通知（非同期）
-                                                let _ = app_handle.emit("clipboard-updated", &text);
-                                            }
-                                            
-                                            // IP検出処理
-                                            if let Ok(_data) = app_data.lock() {
-                                                let detected_ips = Self::extract_ip_addresses(&text);
-                                                drop(_data);
-                                                
-                                                for ip in detected_ips {
-                                                    if let Err(e) = Self::add_ip_to_history(&app_data, ip.clone()) {
-                                                        log::warn!("IP履歴追加エラー: {}", e);
-                                                    } else {
-                                                        log::info!("IP検出・追加: {}", ip);
-                                                        let _ = app_handle.emit("ip-detected", &ip);
-                                                    }
-                                                }
-                                            }
-                                        }
+                                interval = tokio::time::interval(Duration::from_millis(250));
+                                Self::set_status(status, app_handle, |status| status.consecutive_errors = 0);
+                            }
+                            
+                            // パフォーマンス最適化: ハッシュベースの変更検出
+                            let mut hasher = DefaultHasher::new();
+                            text.hash(&mut hasher);
+                            let current_hash = hasher.finish();
+                            
+                            if last_clipboard_hash != Some(current_hash) && !text.trim().is_empty() {
+                                last_clipboard_hash = Some(current_hash);
+                            
+                                // 前回の内容と比較
+                                if let Ok(mut last) = shared.last_content.lock() {
+                                    if last.as_ref() != Some(&text) {
+                                        *last = Some(text.clone());
+                                        changed = Some((text, current_hash));
                                     }
                                 }
                             }
-                            Err(e) => {
-                                consecutive_errors += 1;
-                                log::warn!("クリップボード読み込みエラー #{}: {}", consecutive_errors, e);
-                                
-                                // 連続エラーが多い場合は監視間隔を調整
-                                if consecutive_errors > 5 {
-                                    interval = tokio::time::interval(Duration::from_millis(1000)); // 1秒に延長
-                                    log::warn!("連続エラーが多いため監視間隔を1秒に変更");
-                                }
+                        }
+                        Err(e) => {
+                            consecutive_errors += 1;
+                            log::warn!("クリップボード読み込みエラー #{}: {}", consecutive_errors, e);
+                            Self::record_error(status, app_handle, consecutive_errors, format!("clipboard read failed: {}", e));
+                            
+                            // 連続エラーが多い場合は監視間隔を調整
+                            if consecutive_errors == 6 {
+                                interval = tokio::time::interval(Duration::from_millis(1000)); // 1秒に延長
+                                log::warn!("連続エラーが多いため監視間隔を1秒に変更");
                             }
                         }
                     }
-                    Err(e) => {
-                        consecutive_errors += 1;
-                        log::error!("クリップボードコンテキスト作成エラー #{}: {}", consecutive_errors, e);
-                        
-                        if consecutive_errors > 10 {
-                            log::error!("致命的エラー: クリップボード監視を停止します");
-                            break;
+                }
+                Err(e) => {
+                    consecutive_errors += 1;
+                    log::error!("クリップボードコンテキスト作成エラー #{}: {}", consecutive_errors, e);
+                    let message = format!("clipboard context creation failed: {}", e);
+                    Self::record_error(status, app_handle, consecutive_errors, message.clone());
+                    
+                    if consecutive_errors > 10 {
+                        log::error!("致命的エラー: クリップボード監視ループを終了します");
+                        return Err(message);
+                    }
+                }
+            }
+
+            if let Some((text, current_hash)) = changed {
+                // アプリ自身の書き込みは取り込まず、元アイテムの利用として記録
+                let capture_state = Self::current_capture_state(app_data);
+                if let Some(self_write) = Self::take_self_write(&shared.self_writes, current_hash) {
+                    shared.recorder.record(&text, current_hash, "clipboard", true, &SourceContext::default(), &capture_state);
+                    log::debug!("アプリ自身のクリップボード書き込みを検出: {} chars", text.len());
+                    Self::record_self_write_usage(app_data, &self_write, &text);
+                    continue;
+                }
+
+                // コピー元のアプリは変更を検出した時点で取得する（記録と取り込みで共有）
+                let context = Self::query_source_context(shared).await;
+                shared.recorder.record(&text, current_hash, "clipboard", false, &context, &capture_state);
+
+                if pending.is_some() {
+                    log::debug!("連続したクリップボード変更をまとめます");
+                }
+                let coalesce_window = app_data
+                    .lock()
+                    .map(|data| Duration::from_millis(data.settings.burst.coalesce_window_ms))
+                    .unwrap_or_default();
+                pending = Some((text, context, Instant::now(), coalesce_window));
+            }
+
+            // 待機時間内に次の変更が無ければ取り込む
+            if pending.as_ref().is_some_and(|(_, _, changed_at, window)| changed_at.elapsed() >= *window) {
+                if let Some((text, context, _, _)) = pending.take() {
+                    if Self::handle_capture(shared, app_handle, text, "clipboard", context) {
+                        Self::set_status(status, app_handle, |status| {
+                            status.last_capture_at = Some(Utc::now());
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // エラーを記録（連続エラーの先頭のみ通知してイベントの連発を防ぐ）
+    fn record_error(status: &Arc<Mutex<MonitorStatus>>, app_handle: &AppHandle, consecutive_errors: u32, message: String) {
+        let update = |status: &mut MonitorStatus| {
+            status.consecutive_errors = consecutive_errors;
+            status.total_errors += 1;
+            status.last_error = Some(message);
+            status.last_error_at = Some(Utc::now());
+        };
+
+        if consecutive_errors == 1 {
+            Self::set_status(status, app_handle, update);
+        } else if let Ok(mut status) = status.lock() {
+            update(&mut status);
+        }
+    }
+
+    // 監視ステータスを更新して monitor-status-changed を通知
+    fn set_status<F>(status: &Arc<Mutex<MonitorStatus>>, app_handle: &AppHandle, update: F)
+    where
+        F: FnOnce(&mut MonitorStatus),
+    {
+        let snapshot = match status.lock() {
+            Ok(mut status) => {
+                update(&mut status);
+                status.clone()
+            }
+            Err(_) => return,
+        };
+        let _ = app_handle.emit("monitor-status-changed", &snapshot);
+    }
+
+    pub fn recorder(&self) -> &SessionRecorder {
+        &self.recorder
+    }
+
+    pub fn get_status(&self) -> Result<MonitorStatus, String> {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .map_err(|_| "Failed to lock monitor status".to_string())
+    }
+
+    // アプリ自身がクリップボードへ書き込む前に呼び、監視側で新規取り込みしないようにする
+    // item_id / item_type は書き込み元のアイテム（利用記録に使う）
+    pub fn mark_self_write(&self, content: &str, item_id: Option<String>, item_type: Option<String>) {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+
+        if let Ok(mut self_writes) = self.self_writes.lock() {
+            self_writes.retain(|write| write.written_at.elapsed() < Duration::from_secs(SELF_WRITE_TTL_SECS));
+            self_writes.push(SelfWrite {
+                hash: hasher.finish(),
+                item_id,
+                item_type,
+                written_at: Instant::now(),
+            });
+        }
+    }
+
+    // 内容が一致する自己書き込みの目印を取り出す（期限切れは破棄）
+    fn take_self_write(self_writes: &Arc<Mutex<Vec<SelfWrite>>>, hash: u64) -> Option<SelfWrite> {
+        let mut self_writes = self_writes.lock().ok()?;
+        self_writes.retain(|write| write.written_at.elapsed() < Duration::from_secs(SELF_WRITE_TTL_SECS));
+        let pos = self_writes.iter().position(|write| write.hash == hash)?;
+        Some(self_writes.remove(pos))
+    }
+
+    // 自己書き込みを元アイテムの利用として記録（IDが無い場合は内容が一致するアイテム）
+    fn record_self_write_usage(app_data: &Arc<Mutex<AppData>>, self_write: &SelfWrite, text: &str) {
+        let Ok(mut data) = app_data.lock() else {
+            return;
+        };
+        let item_id = self_write.item_id.as_deref();
+        let now = Utc::now();
+
+        if self_write.item_type.as_deref() != Some("clipboard") {
+            let bookmark = data.bookmarks.iter_mut().find(|bookmark| match item_id {
+                Some(id) => bookmark.id == id,
+                None => bookmark.content == text,
+            });
+            if let Some(bookmark) = bookmark {
+                bookmark.last_accessed = Some(now);
+                data.touch();
+                return;
+            }
+        }
+
+        let item = data.history.iter_mut().find(|item| match item_id {
+            Some(id) => item.id == id,
+            None => item.content == text,
+        });
+        if let Some(item) = item {
+            item.last_accessed = Some(now);
+            data.touch();
+        }
+    }
+
+    fn current_capture_state(app_data: &Arc<Mutex<AppData>>) -> CaptureState {
+        app_data.lock().map(|data| data.capture_state.clone()).unwrap_or_default()
+    }
+
+    // 最前面のアプリと ConcealedType をOSに問い合わせる
+    // osascript の起動を待つ間に監視ループを止めないよう、ブロッキング用のスレッドで実行する
+    async fn query_source_context(shared: &MonitorShared) -> SourceContext {
+        let check_concealed = shared.recorder.is_recording()
+            || shared.app_data.lock().map(|data| data.settings.sensitive.enabled).unwrap_or(false);
+
+        tokio::task::spawn_blocking(move || SourceContext {
+            source_app: Self::detect_source_app(),
+            concealed: check_concealed && SensitiveDetector::is_clipboard_concealed(),
+        })
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("取得元アプリの確認エラー: {}", e);
+            SourceContext::default()
+        })
+    }
+
+    // 新しいクリップボード内容を履歴に取り込み、通知などの副作用を行う（取り込んだ場合true）
+    // selection は取り込み元（"clipboard" | "primary"）
+    fn handle_capture(shared: &MonitorShared, app_handle: &AppHandle, text: String, selection: &str, context: SourceContext) -> bool {
+        let raw_text = text.clone();
+        let result = Self::process_capture(&shared.app_data, CaptureRequest {
+            text,
+            selection,
+            source_app: context.source_app,
+            concealed: context.concealed,
+            now: Utc::now(),
+        });
+
+        // 追加・更新・退避されたアイテムだけを検索インデックスに反映
+        if result.captured || !result.added_ips.is_empty() {
+            if let (Ok(data), Ok(mut index)) = (shared.app_data.lock(), shared.search_index.lock()) {
+                if let Some(item) = result.item_id.as_ref().and_then(|id| data.history.iter().find(|item| &item.id == id)) {
+                    index.upsert(item);
+                }
+                for bookmark in data.bookmarks.iter().filter(|bookmark| result.bookmark_ids.contains(&bookmark.id)) {
+                    index.upsert(bookmark);
+                }
+                for ip_item in data.recent_ips.iter().filter(|ip_item| result.added_ips.contains(&ip_item.ip)) {
+                    index.upsert(ip_item);
+                }
+                index.prune(&data);
+            }
+        }
+
+        // フロントエンドに通知（機密アイテムはマスクして送る）
+        if let Some(content) = &result.notify_content {
+            let _ = app_handle.emit("clipboard-updated", content);
+        }
+
+        if let Some(kind) = result.sensitive_kind {
+            log::info!("機密情報を検出: {}", kind);
+            if result.clear_clipboard_after_secs > 0 {
+                Self::schedule_clipboard_clear(raw_text, result.clear_clipboard_after_secs);
+            }
+        }
+
+        for ip in &result.added_ips {
+            log::info!("IP検出・追加: {}", ip);
+            let _ = app_handle.emit("ip-detected", ip);
+        }
+
+        result.captured
+    }
+
+    // 取り込みパイプライン本体（ルール・機密検出・重複/拡張の統合・退避・IP検出）
+    // OSやフロントエンドに依存しないため、セッション再生ではスクラッチデータに対して実行する
+    pub fn process_capture(app_data: &Arc<Mutex<AppData>>, request: CaptureRequest) -> CaptureResult {
+        let CaptureRequest { text, selection, source_app, concealed, now } = request;
+        let mut result = CaptureResult::default();
+
+        let (sensitive_settings, capture_state, capture_rules, max_item_bytes, burst_settings, primary_detect_ips) = match app_data.lock() {
+            Ok(data) => (
+                data.settings.sensitive.clone(),
+                data.capture_state.clone(),
+                data.settings.capture_rules.clone(),
+                data.settings.eviction.max_item_bytes,
+                data.settings.burst.clone(),
+                data.settings.primary_selection.detect_ips,
+            ),
+            Err(_) => return result,
+        };
+
+        // 一時停止中は取り込まない（前回内容は更新済みなので再開時に取り込まれない）
+        if capture_state.paused {
+            log::debug!("一時停止中のためクリップボード変更を無視: {} chars", text.len());
+            return result;
+        }
+
+        // 1件あたりのサイズ上限
+        if let Some(max_item_bytes) = max_item_bytes {
+            if text.len() > max_item_bytes {
+                log::info!("サイズ上限を超えるため取り込みをスキップ: {} bytes", text.len());
+                return result;
+            }
+        }
+
+        // ユーザー定義ルールの評価
+        let outcome = RuleEngine::evaluate(&capture_rules, &CaptureInput {
+            text: &text,
+            content_type: ContentClassifier::classify(&text),
+            source_app: source_app.as_deref(),
+        });
+        for error in &outcome.errors {
+            log::warn!("キャプチャルール評価エラー: {}", error);
+        }
+        if outcome.ignored {
+            log::info!("キャプチャルールにより無視: {:?}", outcome.matched_rules);
+            return result;
+        }
+
+        // 機密情報の検出（書き換え前の内容で判定）
+        let raw_text = text;
+        let text = outcome.text.clone();
+        let sensitive_kind = if sensitive_settings.enabled {
+            SensitiveDetector::detect(&raw_text).or_else(|| concealed.then_some("concealed"))
+        } else {
+            None
+        };
+
+        // シークレットモード中は保存済みのアイテム・ブックマーク・IP履歴を変更しない
+        // （利用回数や置き換えた内容が次の保存でディスクに書き出されるため）
+        let incognito = capture_state.incognito;
+        let is_writable = |item: &ClipboardItem| !incognito || item.incognito;
+
+        // 履歴に追加
+        if let Ok(mut data) = app_data.lock() {
+            // 選択範囲を広げながらのコピーは直前のアイテムを置き換える
+            let extended_pos = if burst_settings.collapse_extensions {
+                Self::find_extended_item(&data.history, &text, burst_settings.extension_window_secs, now)
+                    .filter(|&pos| is_writable(&data.history[pos]))
+            } else {
+                None
+            };
+
+            // 同じ内容のアイテムがあれば、IDと利用情報を保ったまま先頭に移動
+            let existing_pos = data.history.iter().position(|item| item.content == text);
+            let item = match (existing_pos, extended_pos) {
+                (Some(pos), _) if !is_writable(&data.history[pos]) => {
+                    log::debug!("シークレットモード中のため保存済みアイテムを更新しません: {}", data.history[pos].id);
+                    return result;
+                }
+                (Some(pos), _) => {
+                    let mut item = data.history.remove(pos);
+                    item.record_copy(now);
+                    Self::merge_capture_metadata(&mut item, &outcome.tags, &source_app);
+                    log::info!("既存アイテムを更新: {} (copy_count: {})", item.id, item.copy_count);
+                    item
+                }
+                (None, Some(pos)) => {
+                    let mut item = data.history.remove(pos);
+                    log::info!("選択範囲の拡張として直前のアイテムを置き換え: {} -> {} chars", item.content.len(), text.len());
+                    item.replace_content(text.clone(), ContentClassifier::classify(&text).to_string(), now);
+                    item.sensitive_kind = sensitive_kind.map(|kind| kind.to_string());
+                    // ルールは拡張後の内容で評価しているため、新規アイテムと同じく反映する
+                    Self::merge_capture_metadata(&mut item, &outcome.tags, &source_app);
+                    item.selection = selection.to_string();
+                    item
+                }
+                (None, None) => {
+                    let mut item = ClipboardItem::captured_at(text.clone(), ContentClassifier::classify(&text).to_string(), now);
+                    item.sensitive_kind = sensitive_kind.map(|kind| kind.to_string());
+                    item.incognito = incognito;
+                    item.tags = outcome.tags.clone();
+                    item.source_app = source_app.clone();
+                    item.selection = selection.to_string();
+                    item
+                }
+            };
+            result.notify_content = Some(item.masked().content);
+
+            // 退避ポリシーを適用（ピン留めと追加したアイテムは除外）
+            let item_id = item.id.clone();
+            result.item_id = Some(item_id.clone());
+            data.history.push(item);
+            Eviction::apply(&mut data, Some(&item_id));
+            data.touch();
+            log::info!("クリップボード変更検出: {} chars", text.len());
+
+            // ルールによる自動ブックマーク（機密アイテムとシークレットモード中は永続化しないため対象外）
+            if sensitive_kind.is_none() && !incognito {
+                for set in &outcome.bookmark_sets {
+                    if let Some(bookmark_id) = Self::auto_bookmark(&mut data, &text, set) {
+                        result.bookmark_ids.push(bookmark_id);
+                    }
+                }
+            }
+        }
+        result.captured = true;
+
+        // 機密アイテムはIP検出を行わない
+        if sensitive_kind.is_some() {
+            result.sensitive_kind = sensitive_kind;
+            result.clear_clipboard_after_secs = sensitive_settings.clear_clipboard_after_secs;
+            return result;
+        }
+
+        // PRIMARY選択は設定で有効な場合のみIP検出を行う（シークレットモード中は行わない）
+        if incognito || outcome.skip_ip_detection || (selection == "primary" && !primary_detect_ips) {
+            return result;
+        }
+
+        // IP検出処理
+        let detected_ips = Self::extract_ip_addresses(&text);
+        for ip in detected_ips {
+            if let Err(e) = Self::add_ip_to_history(app_data, ip.clone()) {
+                log::warn!("IP履歴追加エラー: {}", e);
+            } else {
+                result.added_ips.push(ip);
+            }
+        }
+
+        result
+    }
+
+    // 再取り込みしたアイテムにルールのタグと取り込み元アプリを反映する（既存のタグは残す）
+    fn merge_capture_metadata(item: &mut ClipboardItem, tags: &[String], source_app: &Option<String>) {
+        for tag in tags {
+            if !item.tags.contains(tag) {
+                item.tags.push(tag.clone());
+            }
+        }
+        if source_app.is_some() {
+            item.source_app = source_app.clone();
+        }
+    }
+
+    // 直前のアイテムが一定時間内にコピーされ、新しい内容がその前後への拡張であれば位置を返す
+    fn find_extended_item(history: &[ClipboardItem], text: &str, window_secs: u64, now: DateTime<Utc>) -> Option<usize> {
+        let pos = history.len().checked_sub(1)?;
+        let previous = &history[pos];
+
+        let last_copied = previous.last_copied.unwrap_or(previous.timestamp);
+        let within_window = now - last_copied <= chrono::Duration::seconds(window_secs as i64);
+        let is_extension = text.len() > previous.content.len()
+            && (text.starts_with(&previous.content) || text.ends_with(&previous.content));
+
+        (within_window && is_extension && !previous.pinned).then_some(pos)
+    }
+
+    // 同じセットに同じ内容が無ければブックマークを追加し、そのIDを返す
+    fn auto_bookmark(data: &mut AppData, text: &str, set: &str) -> Option<String> {
+        let exists = data
+            .bookmarks
+            .iter()
+            .any(|bookmark| bookmark.content == text && bookmark.tags.iter().any(|tag| tag == set));
+        if exists {
+            return None;
+        }
+
+        let name: String = text.lines().next().unwrap_or_default().chars().take(40).collect();
+        let bookmark = BookmarkItem::new(name, text.to_string(), "text".to_string(), vec![set.to_string()]);
+        let bookmark_id = bookmark.id.clone();
+        data.bookmarks.push(bookmark);
+        log::info!("キャプチャルールにより自動ブックマーク: {}", set);
+        Some(bookmark_id)
+    }
+
+    // 最前面のアプリ名を取得（macOS専用）
+    #[cfg(target_os = "macos")]
+    pub fn detect_source_app() -> Option<String> {
+        use std::process::Command;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg("tell application \"System Events\" to get name of first application process whose frontmost is true")
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if name.is_empty() { None } else { Some(name) }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn detect_source_app() -> Option<String> {
+        None
+    }
+
+    fn check_pause_expiry(app_data: &Arc<Mutex<AppData>>, app_handle: &AppHandle) {
+        let resumed_state = match app_data.lock() {
+            Ok(mut data) => data.capture_state.resume_if_expired().then(|| {
+                data.touch();
+                data.capture_state.clone()
+            }),
+            Err(_) => None,
+        };
+
+        if let Some(state) = resumed_state {
+            log::info!("一時停止期間が終了したため監視を再開しました");
+            Self::publish_capture_state(app_handle, &state);
+        }
+    }
+
+    // キャプチャ状態を更新し、フロントエンドとトレイに反映
+    pub fn update_capture_state<F>(&self, app_handle: &AppHandle, update: F) -> Result<CaptureState, String>
+    where
+        F: FnOnce(&mut CaptureState),
+    {
+        let state = {
+            let mut data = self.app_data.lock().map_err(|_| "Failed to lock app data")?;
+            update(&mut data.capture_state);
+            data.touch();
+            data.capture_state.clone()
+        };
+
+        Self::publish_capture_state(app_handle, &state);
+        Ok(state)
+    }
+
+    pub fn publish_capture_state(app_handle: &AppHandle, state: &CaptureState) {
+        let _ = app_handle.emit("capture-state-changed", state);
+        TrayManager::new(app_handle.clone()).refresh(state);
+    }
+
+    // 指定秒数後、内容が変わっていなければシステムクリップボードを消去
+    fn schedule_clipboard_clear(text: String, after_secs: u64) {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(after_secs)).await;
+
+            if let Ok(mut ctx) = ClipboardContext::new() {
+                if ctx.get_contents().ok().as_ref() == Some(&text) {
+                    if let Err(e) = ctx.set_contents(String::new()) {
+                        log::warn!("クリップボード消去エラー: {}", e);
+                    } else {
+                        log::info!("機密情報をクリップボードから消去しました");
+                    }
+                }
+            }
+        });
+    }
+
+    // 機密アイテムをTTL経過後に履歴から削除
+    pub fn start_sensitive_expiry(&self) {
+        let app_data = Arc::clone(&self.app_data);
+        let search_index = Arc::clone(&self.search_index);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(15));
+
+            loop {
+                interval.tick().await;
+
+                if let Ok(mut data) = app_data.lock() {
+                    let ttl_secs = data.settings.sensitive.item_ttl_secs;
+                    if ttl_secs == 0 {
+                        continue;
+                    }
+
+                    let cutoff = Utc::now() - chrono::Duration::seconds(ttl_secs as i64);
+                    let original_count = data.history.len();
+                    data.history.retain(|item| !item.is_sensitive_expired(cutoff));
+
+                    let expired = original_count - data.history.len();
+                    if expired > 0 {
+                        data.touch();
+                        if let Ok(mut index) = search_index.lock() {
+                            index.prune(&data);
                         }
+                        log::info!("期限切れの機密アイテム{}件を削除しました", expired);
                     }
                 }
             }
         });
-        
-        Ok(())
+    }
+
+    // 保持ポリシーを起動時と設定した間隔ごとに適用する
+    pub fn start_retention_scheduler(&self, app_handle: AppHandle) {
+        let app_data = Arc::clone(&self.app_data);
+        let search_index = Arc::clone(&self.search_index);
+
+        tokio::spawn(async move {
+            loop {
+                let settings = match app_data.lock() {
+                    Ok(data) => data.settings.retention.clone(),
+                    Err(_) => RetentionSettings::default(),
+                };
+
+                if settings.enabled {
+                    if let Err(e) = Self::run_retention(&app_data, &search_index, &app_handle) {
+                        log::warn!("保持ポリシー適用エラー: {}", e);
+                    }
+                }
+
+                tokio::time::sleep(Duration::from_secs(settings.interval_minutes.max(1) * 60)).await;
+            }
+        });
+    }
+
+    // 保持ポリシーを適用し、実行結果をログに記録する
+    pub fn run_retention(
+        app_data: &Arc<Mutex<AppData>>,
+        search_index: &Arc<Mutex<SearchIndex>>,
+        app_handle: &AppHandle,
+    ) -> Result<RetentionPreview, String> {
+        let log_paths = FileManager::list_log_files(app_handle).unwrap_or_else(|e| {
+            log::warn!("ログファイル一覧の取得エラー: {}", e);
+            Vec::new()
+        });
+
+        let preview = {
+            let mut data = app_data.lock().map_err(|_| "Failed to lock app data")?;
+            let settings = data.settings.retention.clone();
+            let preview = Retention::plan(&data, &log_paths, &settings, Utc::now());
+            Retention::apply_to_data(&mut data, &preview);
+            data.touch();
+            if let Ok(mut index) = search_index.lock() {
+                index.prune(&data);
+            }
+            preview
+        };
+        let removed_logs = Retention::remove_log_files(&preview);
+
+        let message = format!(
+            "保持ポリシーを適用: 履歴 {} 件, IP {} 件, ログ {} ファイルを削除 ({} bytes)",
+            preview.history.len(),
+            preview.recent_ips.len(),
+            removed_logs,
+            preview.total_bytes
+        );
+        log::info!("{}", message);
+        FileManager::log_to_file(app_handle, "INFO", &message);
+
+        Ok(preview)
     }
 
     pub fn stop_monitoring(&self) -> Result<(), String> {
@@ -288,6 +1018,7 @@ impl ClipboardMonitor {
         
         // IPを最新順にソート
         data.recent_ips.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        data.touch();
         
         Ok(())
     }
@@ -295,25 +1026,72 @@ impl ClipboardMonitor {
     pub fn add_item(&self, content: String, content_type: String) -> Result<(), String> {
         let mut data = self.app_data.lock().map_err(|_| "Failed to lock app data")?;
         
-        let item = ClipboardItem {
-            id: Uuid::new_v4().to_string(),
-            content,
-            content_type,
-            timestamp: Utc::now(),
-            size: 0, // サイズは後で計算
-            access_count: 0,
-            last_accessed: None,
-        };
-        
-        // 設定で指定された件数制限
-        let limit = data.settings.history_limit;
-        if data.history.len() >= limit {
-            data.history.remove(0);
+        if data.settings.eviction.max_item_bytes.is_some_and(|max| content.len() > max) {
+            return Err("Item exceeds the maximum item size".to_string());
         }
         
+        let mut item = ClipboardItem::new(content, content_type);
+        item.incognito = data.capture_state.incognito;
+        
+        // 退避ポリシーを適用（ピン留めと追加したアイテムは除外）
+        let item_id = item.id.clone();
         data.history.push(item);
+        Eviction::apply(&mut data, Some(&item_id));
+        data.touch();
+        if let Ok(mut index) = self.search_index.lock() {
+            if let Some(item) = data.history.iter().find(|item| item.id == item_id) {
+                index.upsert(item);
+            }
+            index.prune(&data);
+        }
         log::info!("クリップボード履歴に追加: {} chars", data.history.last().unwrap().size);
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CaptureRule, RuleAction, RuleConditions};
+
+    fn capture(app_data: &Arc<Mutex<AppData>>, text: &str, selection: &str, source_app: &str, now: DateTime<Utc>) -> CaptureResult {
+        ClipboardMonitor::process_capture(app_data, CaptureRequest {
+            text: text.to_string(),
+            selection,
+            source_app: Some(source_app.to_string()),
+            concealed: false,
+            now,
+        })
+    }
+
+    #[test]
+    fn extension_applies_rules_matching_only_the_extended_text() {
+        let mut data = AppData::default();
+        data.settings.capture_rules = vec![CaptureRule {
+            id: "todo".to_string(),
+            name: "TODO".to_string(),
+            enabled: true,
+            conditions: RuleConditions { pattern: Some("TODO".to_string()), ..RuleConditions::default() },
+            actions: vec![
+                RuleAction::AddTags { tags: vec!["todo".to_string()] },
+                RuleAction::AutoBookmark { set: "todos".to_string() },
+            ],
+            stop_processing: false,
+        }];
+        let app_data = Arc::new(Mutex::new(data));
+        let now = Utc::now();
+
+        capture(&app_data, "fix the parser", "clipboard", "Terminal", now);
+        capture(&app_data, "TODO: fix the parser", "primary", "Editor", now + chrono::Duration::seconds(1));
+
+        let data = app_data.lock().unwrap();
+        assert_eq!(data.history.len(), 1);
+        let item = &data.history[0];
+        assert_eq!(item.content, "TODO: fix the parser");
+        assert_eq!(item.tags, vec!["todo".to_string()]);
+        assert_eq!(item.source_app.as_deref(), Some("Editor"));
+        assert_eq!(item.selection, "primary");
+        assert!(data.bookmarks.iter().any(|bookmark| bookmark.tags.contains(&"todos".to_string())));
+    }
+}
@@ -1,18 +1,42 @@
 use tauri::{AppHandle, State, Manager};
 use tauri_plugin_global_shortcut::GlobalShortcutExt;
 use chrono::Utc;
-use crate::models::AppData;
+use crate::models::{AppData, ContentChunk, ReplayReport, RetentionPreview, SessionRecording, SessionRecordingStatus, UsageEvent};
 use crate::ClipboardManager;
 use crate::file_manager::FileManager;
+use crate::pagination::Paginator;
+use crate::retention::Retention;
+use crate::session_recorder::SessionRecorder;
 
 #[tauri::command]
 pub fn get_app_data(state: State<'_, ClipboardManager>) -> Result<AppData, String> {
     match state.app_data.lock() {
-        Ok(data) => Ok(data.clone()),
+        Ok(data) => Ok(data.masked()),
         Err(_) => Err("Failed to access app data".to_string()),
     }
 }
 
+// 1件の本文を取得する（kind: "history" | "bookmark"）
+// 大きな本文は offset・length（バイト単位）で分割して取得できる
+#[tauri::command]
+pub fn get_item_content(
+    kind: String,
+    item_id: String,
+    offset: Option<usize>,
+    length: Option<usize>,
+    state: State<'_, ClipboardManager>,
+) -> Result<ContentChunk, String> {
+    let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+    let content = match kind.as_str() {
+        "history" => data.history.iter().find(|item| item.id == item_id).map(|item| &item.content),
+        "bookmark" => data.bookmarks.iter().find(|bookmark| bookmark.id == item_id).map(|bookmark| &bookmark.content),
+        _ => return Err(format!("Unknown item kind: {}", kind)),
+    }
+    .ok_or("Item not found")?;
+
+    Paginator::chunk(&item_id, content, offset, length)
+}
+
 #[tauri::command]
 pub fn save_data_to_file(
     state: State<'_, ClipboardManager>,
@@ -28,6 +52,14 @@ pub fn load_data_from_file(
     app_handle: AppHandle,
 ) -> Result<String, String> {
     state.load_from_file(&app_handle)?;
+
+    // 読み込んだキャプチャ状態をトレイ・画面に反映
+    if let Err(e) = state.update_capture_state(&app_handle, |capture_state| {
+        capture_state.resume_if_expired();
+    }) {
+        log::warn!("キャプチャ状態の反映エラー: {}", e);
+    }
+
     Ok("Data loaded successfully".to_string())
 }
 
@@ -56,6 +88,7 @@ pub fn get_app_diagnostics(
     };
     
     let file_stats = FileManager::get_file_stats(&app_handle)?;
+    let monitor_status = state.get_monitor_status().ok();
     
     let mut diagnostics = serde_json::json!({
         "version": env!("CARGO_PKG_VERSION"),
@@ -72,6 +105,7 @@ pub fn get_app_diagnostics(
         "health": {
             "data_integrity": "OK",
             "memory_usage": "Normal",
+            "monitor": monitor_status,
         }
     });
     
@@ -87,70 +121,35 @@ pub fn get_app_diagnostics(
     Ok(diagnostics)
 }
 
-// メモリクリーンアップ用の新しいコマンド
+// 保持ポリシーで削除される対象を確認（実際には削除しない）
 #[tauri::command]
-pub fn cleanup_memory(
+pub fn preview_retention(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
-    size_threshold_mb: Option<f64>,
-) -> Result<String, String> {
-    let threshold_bytes = (size_threshold_mb.unwrap_or(1.0) * 1024.0 * 1024.0) as usize;
-    
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let original_count = data.history.len();
-            
-            // 大きなアイテムを削除
-            data.history.retain(|item| item.size <= threshold_bytes);
-            
-            let cleaned_items = original_count - data.history.len();
-            
-            if cleaned_items > 0 {
-                log::info!("メモリ最適化: {} 件のアイテムを削除", cleaned_items);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-            }
+) -> Result<RetentionPreview, String> {
+    let log_paths = FileManager::list_log_files(&app_handle)?;
 
-            Ok(format!("Cleaned {} large items (>{:.1}MB)", cleaned_items, size_threshold_mb.unwrap_or(1.0)))
-        }
-        Err(_) => Err("Failed to access app data for cleanup".to_string()),
+    match state.app_data.lock() {
+        Ok(data) => Ok(Retention::plan(&data, &log_paths, &data.settings.retention, Utc::now())),
+        Err(_) => Err("Failed to access app data".to_string()),
     }
 }
 
+// 保持ポリシーを今すぐ適用（定期実行を待たずに削除する）
 #[tauri::command]
-pub fn cleanup_old_items(
+pub fn apply_retention(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
-    days_old: Option<i64>,
-) -> Result<String, String> {
-    let cutoff_days = days_old.unwrap_or(30);
-    let cutoff_date = Utc::now() - chrono::Duration::days(cutoff_days);
-    
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let original_count = data.history.len();
-            
-            // 古いアイテムを削除
-            data.history.retain(|item| item.timestamp > cutoff_date);
-            
-            let removed_count = original_count - data.history.len();
-            
-            if removed_count > 0 {
-                log::info!("古いアイテム削除: {} 日以前の {} 件削除", days_old.unwrap_or(30), removed_count);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-            }
+) -> Result<RetentionPreview, String> {
+    let preview = state.run_retention(&app_handle)?;
 
-            Ok(format!("Removed {} items older than {} days", removed_count, cutoff_days))
+    if !preview.history.is_empty() || !preview.recent_ips.is_empty() {
+        if let Err(e) = state.save_to_file(&app_handle) {
+            log::warn!("自動保存エラー: {}", e);
         }
-        Err(_) => Err("Failed to access app data for cleanup".to_string()),
     }
+
+    Ok(preview)
 }
 
 // ホットキー管理
@@ -323,43 +322,124 @@ pub fn update_item_access(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let updated = match item_type.as_str() {
-                "clipboard" => {
-                    if let Some(item) = data.history.iter_mut().find(|item| item.id == item_id) {
-                        item.access_count += 1;
-                        item.last_accessed = Some(Utc::now());
-                        true
-                    } else {
-                        false
-                    }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+        let updated = match item_type.as_str() {
+            "clipboard" => {
+                if let Some(item) = data.history.iter_mut().find(|item| item.id == item_id) {
+                    item.access_count += 1;
+                    item.last_accessed = Some(Utc::now());
+                    true
+                } else {
+                    false
                 }
-                "bookmark" => {
-                    if let Some(item) = data.bookmarks.iter_mut().find(|item| item.id == item_id) {
-                        item.access_count += 1;
-                        item.last_accessed = Some(Utc::now());
-                        true
-                    } else {
-                        false
-                    }
+            }
+            "bookmark" => {
+                if let Some(item) = data.bookmarks.iter_mut().find(|item| item.id == item_id) {
+                    item.access_count += 1;
+                    item.last_accessed = Some(Utc::now());
+                    true
+                } else {
+                    false
                 }
-                _ => false
-            };
+            }
+            _ => false
+        };
+        if !updated {
+            return Err("Item not found".to_string());
+        }
+        data.touch();
+    }
+    log::info!("アクセス回数を更新: {} ({})", item_id, item_type);
 
-            if updated {
-                log::info!("アクセス回数を更新: {} ({})", item_id, item_type);
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
 
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
+    Ok("Access count updated successfully".to_string())
+}
 
-                Ok("Access count updated successfully".to_string())
-            } else {
-                Err("Item not found".to_string())
-            }
+// アイテムの利用履歴（コピー・貼り付け）を取得
+#[tauri::command]
+pub fn get_item_timeline(
+    item_id: String,
+    item_type: String, // "clipboard" or "bookmark"
+    state: State<'_, ClipboardManager>,
+) -> Result<Vec<UsageEvent>, String> {
+    match state.app_data.lock() {
+        Ok(data) => {
+            let timeline = match item_type.as_str() {
+                "clipboard" => data.history.iter().find(|item| item.id == item_id).map(|item| item.timeline.clone()),
+                "bookmark" => data.bookmarks.iter().find(|item| item.id == item_id).map(|item| item.timeline.clone()),
+                _ => return Err("Unknown item type".to_string()),
+            };
+
+            timeline.ok_or_else(|| "Item not found".to_string())
         }
         Err(_) => Err("Failed to access app data".to_string()),
     }
-}
\ No newline at end of file
+}
+
+// クリップボードセッションの記録を開始（デバッグ用）
+// include_content を有効にすると内容も記録する（再生には内容が必要）
+#[tauri::command]
+pub fn start_session_recording(
+    include_content: Option<bool>,
+    state: State<'_, ClipboardManager>,
+) -> Result<SessionRecordingStatus, String> {
+    let settings = state
+        .app_data
+        .lock()
+        .map(|data| data.settings.clone())
+        .map_err(|_| "Failed to access app data".to_string())?;
+
+    state.session_recorder().start(settings, include_content.unwrap_or(false))
+}
+
+// 記録を終了してファイルに保存し、保存先のパスを返す
+#[tauri::command]
+pub fn stop_session_recording(
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let recording = state.session_recorder().stop()?;
+
+    let file_name = format!("session-{}.json", recording.started_at.format("%Y%m%d-%H%M%S"));
+    let file_path = FileManager::get_recordings_dir(&app_handle)?.join(file_name);
+    let json_content = serde_json::to_string_pretty(&recording)
+        .map_err(|e| format!("Failed to serialize recording: {}", e))?;
+    std::fs::write(&file_path, json_content)
+        .map_err(|e| format!("Failed to write recording: {}", e))?;
+
+    log::info!("セッション記録を保存: {:?}", file_path);
+    Ok(file_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn get_session_recording_status(state: State<'_, ClipboardManager>) -> Result<SessionRecordingStatus, String> {
+    Ok(state.session_recorder().status())
+}
+
+// 記録ファイルをスクラッチデータに対して再生（実際の履歴は変更しない）
+// use_current_settings を有効にすると記録時ではなく現在の設定で再生する
+#[tauri::command]
+pub fn replay_session_recording(
+    path: String,
+    use_current_settings: Option<bool>,
+    state: State<'_, ClipboardManager>,
+) -> Result<ReplayReport, String> {
+    let file_content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    let recording: SessionRecording = serde_json::from_str(&file_content)
+        .map_err(|e| format!("Invalid recording file: {}", e))?;
+
+    let settings = if use_current_settings.unwrap_or(false) {
+        let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+        Some(data.settings.clone())
+    } else {
+        None
+    };
+
+    Ok(SessionRecorder::replay(&recording, settings))
+}
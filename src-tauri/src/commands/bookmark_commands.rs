@@ -1,7 +1,12 @@
 use tauri::{AppHandle, State};
 use uuid::Uuid;
 use chrono::Utc;
-use crate::models::BookmarkItem;
+use crate::bookmark_folders::{DeleteMode, FolderTree};
+use crate::models::{AppData, BookmarkFolder, BookmarkItem, FolderContents, ItemSummary, Page, SearchResult};
+use crate::pagination::Paginator;
+use crate::query::QueryParser;
+use crate::search::Search;
+use crate::sort::{SortMode, Sorter};
 use crate::ClipboardManager;
 
 #[tauri::command]
@@ -12,40 +17,72 @@ pub fn get_bookmarks(state: State<'_, ClipboardManager>) -> Result<Vec<BookmarkI
     }
 }
 
+// 指定の順序でブックマークを返す（sort_by: recent | frequency | frecency | alphabetical | name | size）
+// alphabetical は内容、name は名前で比較する
+// folder_id を指定した場合はそのフォルダ直下のブックマークに絞り込む（空文字はルート直下）
+#[tauri::command]
+pub fn get_sorted_bookmarks(
+    sort_by: String,
+    folder_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<Vec<BookmarkItem>, String> {
+    let mode = SortMode::parse(&sort_by)?;
+    let data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+
+    let mut bookmarks = bookmarks_in_folder(&data, folder_id.as_deref())?;
+    Sorter::sort(&mut bookmarks, mode, Utc::now(), |bookmark| &bookmark.content);
+    Ok(bookmarks.into_iter().cloned().collect())
+}
+
+// ブックマークを指定の順序（省略時は recent）で1ページずつ要約として返す
+// folder_id の扱いは get_sorted_bookmarks と同じ
+#[tauri::command]
+pub fn list_bookmarks(
+    cursor: Option<String>,
+    limit: Option<usize>,
+    sort_by: Option<String>,
+    folder_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<Page<ItemSummary>, String> {
+    let mode = SortMode::parse(sort_by.as_deref().unwrap_or("recent"))?;
+    let data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+
+    let mut bookmarks = bookmarks_in_folder(&data, folder_id.as_deref())?;
+    Sorter::sort(&mut bookmarks, mode, Utc::now(), |bookmark| &bookmark.content);
+    Paginator::page(&bookmarks, cursor.as_deref(), limit, |bookmark| &bookmark.id, |bookmark| bookmark.summary())
+}
+
+// folder_id を省略した場合はルートに追加する
 #[tauri::command]
 pub fn add_bookmark(
     name: String,
     content: String,
     content_type: String,
     tags: Vec<String>,
+    folder_id: Option<String>,
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    let bookmark = BookmarkItem {
-        id: Uuid::new_v4().to_string(),
-        name,
-        content,
-        content_type,
-        timestamp: Utc::now(),
-        tags,
-        access_count: 0,
-        last_accessed: None,
-    };
-
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            data.bookmarks.push(bookmark);
-            log::info!("ブックマークを追加しました");
+    let mut bookmark = BookmarkItem::new(name, content, content_type, tags);
 
-            // 自動保存
-            if let Err(e) = state.save_to_file(&app_handle) {
-                log::warn!("自動保存エラー: {}", e);
-            }
-
-            Ok("Bookmark added successfully".to_string())
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        FolderTree::ensure_exists(&data.bookmark_folders, folder_id.as_deref())?;
+        bookmark.folder_id = folder_id;
+        if let Ok(mut index) = state.search_index.lock() {
+            index.upsert(&bookmark);
         }
-        Err(_) => Err("Failed to access bookmarks".to_string()),
+        data.bookmarks.push(bookmark);
+        data.touch();
     }
+    log::info!("ブックマークを追加しました");
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Bookmark added successfully".to_string())
 }
 
 #[tauri::command]
@@ -54,24 +91,27 @@ pub fn delete_bookmark(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(pos) = data.bookmarks.iter().position(|b| b.id == bookmark_id) {
-                data.bookmarks.remove(pos);
-                log::info!("ブックマークを削除しました: {}", bookmark_id);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-
-                Ok("Bookmark deleted successfully".to_string())
-            } else {
-                Err("Bookmark not found".to_string())
-            }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        let pos = data
+            .bookmarks
+            .iter()
+            .position(|b| b.id == bookmark_id)
+            .ok_or("Bookmark not found")?;
+        data.bookmarks.remove(pos);
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.remove("bookmark", &bookmark_id);
         }
-        Err(_) => Err("Failed to access bookmarks".to_string()),
     }
+    log::info!("ブックマークを削除しました: {}", bookmark_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Bookmark deleted successfully".to_string())
 }
 
 #[tauri::command]
@@ -83,61 +123,64 @@ pub fn update_bookmark(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(bookmark) = data.bookmarks.iter_mut().find(|b| b.id == bookmark_id) {
-                if let Some(new_name) = name {
-                    bookmark.name = new_name;
-                }
-                if let Some(new_content) = content {
-                    bookmark.content = new_content;
-                }
-                if let Some(new_tags) = tags {
-                    bookmark.tags = new_tags;
-                }
-                bookmark.last_accessed = Some(Utc::now());
-
-                log::info!("ブックマークを更新: {}", bookmark_id);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-
-                Ok("Bookmark updated successfully".to_string())
-            } else {
-                Err("Bookmark not found".to_string())
-            }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        let bookmark = data
+            .bookmarks
+            .iter_mut()
+            .find(|b| b.id == bookmark_id)
+            .ok_or("Bookmark not found")?;
+        if let Some(new_name) = name {
+            bookmark.name = new_name;
         }
-        Err(_) => Err("Failed to access bookmarks".to_string()),
+        if let Some(new_content) = content {
+            bookmark.content = new_content;
+        }
+        if let Some(new_tags) = tags {
+            bookmark.tags = new_tags;
+        }
+        bookmark.last_accessed = Some(Utc::now());
+        if let Ok(mut index) = state.search_index.lock() {
+            index.upsert(&*bookmark);
+        }
+        data.touch();
+    }
+
+    log::info!("ブックマークを更新: {}", bookmark_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
     }
+
+    Ok("Bookmark updated successfully".to_string())
 }
 
+// 検索クエリ（あいまい一致・条件指定）で検索し、一致範囲を返す
+// 名前・タグは本文より高く評価する
 #[tauri::command]
 pub fn search_bookmarks(
     query: String,
     state: State<'_, ClipboardManager>,
-) -> Result<Vec<BookmarkItem>, String> {
+) -> Result<Vec<SearchResult<BookmarkItem>>, String> {
+    let parsed = QueryParser::parse(&query).map_err(|e| e.to_string())?;
+
     match state.app_data.lock() {
         Ok(data) => {
-            if query.trim().is_empty() {
-                return Ok(data.bookmarks.clone());
+            if parsed.is_empty() {
+                return Ok(data
+                    .bookmarks
+                    .iter()
+                    .map(|bookmark| SearchResult { item: bookmark.clone(), score: 0.0, matches: Vec::new() })
+                    .collect());
             }
 
-            let query_lower = query.to_lowercase();
-            let mut results: Vec<BookmarkItem> = data
-                .bookmarks
-                .iter()
-                .filter(|bookmark| {
-                    bookmark.name.to_lowercase().contains(&query_lower)
-                        || bookmark.content.to_lowercase().contains(&query_lower)
-                        || bookmark.tags.iter().any(|tag| tag.to_lowercase().contains(&query_lower))
-                })
-                .cloned()
+            let index = state.synced_search_index(&data)?;
+            let candidates = index.candidates(&parsed, "bookmark");
+            let results: Vec<SearchResult<BookmarkItem>> = Search::rank(&data.bookmarks, &parsed, candidates.as_ref(), &data.settings.search)
+                .into_iter()
+                .map(|(item, score, matches)| SearchResult { item: item.clone(), score, matches })
                 .collect();
-
-            // 最新順でソート
-            results.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
             
             log::info!("ブックマーク検索: '{}' -> {} 件", query, results.len());
             Ok(results)
@@ -152,31 +195,34 @@ pub fn duplicate_bookmark(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(original) = data.bookmarks.iter().find(|b| b.id == bookmark_id).cloned() {
-                let mut duplicate = original;
-                duplicate.id = Uuid::new_v4().to_string();
-                duplicate.name = format!("{} (コピー)", duplicate.name);
-                duplicate.timestamp = Utc::now();
-                duplicate.access_count = 0;
-                duplicate.last_accessed = None;
-
-                data.bookmarks.push(duplicate);
-                log::info!("ブックマークを複製: {}", bookmark_id);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-
-                Ok("Bookmark duplicated successfully".to_string())
-            } else {
-                Err("Bookmark not found".to_string())
-            }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        let mut duplicate = data
+            .bookmarks
+            .iter()
+            .find(|b| b.id == bookmark_id)
+            .cloned()
+            .ok_or("Bookmark not found")?;
+        duplicate.id = Uuid::new_v4().to_string();
+        duplicate.name = format!("{} (コピー)", duplicate.name);
+        duplicate.timestamp = Utc::now();
+        duplicate.access_count = 0;
+        duplicate.last_accessed = None;
+
+        if let Ok(mut index) = state.search_index.lock() {
+            index.upsert(&duplicate);
         }
-        Err(_) => Err("Failed to access bookmarks".to_string()),
+        data.bookmarks.push(duplicate);
+        data.touch();
     }
+    log::info!("ブックマークを複製: {}", bookmark_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Bookmark duplicated successfully".to_string())
 }
 
 #[tauri::command]
@@ -184,21 +230,24 @@ pub fn clear_all_bookmarks(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let count = data.bookmarks.len();
-            data.bookmarks.clear();
-            log::info!("全ブックマークをクリア: {} items", count);
-
-            // 自動保存
-            if let Err(e) = state.save_to_file(&app_handle) {
-                log::warn!("自動保存エラー: {}", e);
-            }
-
-            Ok(format!("Cleared {} bookmarks", count))
+    let count = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        let count = data.bookmarks.len();
+        data.bookmarks.clear();
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.prune(&data);
         }
-        Err(_) => Err("Failed to access bookmarks".to_string()),
+        count
+    };
+    log::info!("全ブックマークをクリア: {} items", count);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
     }
+
+    Ok(format!("Cleared {} bookmarks", count))
 }
 
 #[tauri::command]
@@ -244,4 +293,237 @@ pub fn find_duplicate_bookmarks(
         }
         Err(_) => Err("Failed to access bookmark data".to_string()),
     }
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub fn get_bookmark_folders(state: State<'_, ClipboardManager>) -> Result<Vec<BookmarkFolder>, String> {
+    match state.app_data.lock() {
+        Ok(data) => Ok(data.bookmark_folders.clone()),
+        Err(_) => Err("Failed to access bookmark folders".to_string()),
+    }
+}
+
+// フォルダ直下の子フォルダとブックマークを返す（folder_id を省略した場合はルート）
+#[tauri::command]
+pub fn get_folder_contents(
+    folder_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<FolderContents, String> {
+    let data = state.app_data.lock().map_err(|_| "Failed to access bookmark folders")?;
+    let folder = match folder_id.as_deref() {
+        Some(folder_id) => Some(FolderTree::find(&data.bookmark_folders, folder_id).ok_or("Folder not found")?.clone()),
+        None => None,
+    };
+
+    let mut folders: Vec<BookmarkFolder> = data
+        .bookmark_folders
+        .iter()
+        .filter(|child| child.parent_id == folder_id)
+        .cloned()
+        .collect();
+    folders.sort_by_cached_key(|child| Sorter::collation_key(&child.name));
+
+    let mut bookmarks: Vec<BookmarkItem> = data
+        .bookmarks
+        .iter()
+        .filter(|bookmark| bookmark.folder_id == folder_id)
+        .cloned()
+        .collect();
+    bookmarks.sort_by_cached_key(|bookmark| Sorter::collation_key(&bookmark.name));
+
+    Ok(FolderContents {
+        path: folder_id.as_deref().map(|id| FolderTree::path(&data.bookmark_folders, id)).unwrap_or_default(),
+        folder,
+        folders,
+        bookmarks,
+    })
+}
+
+// parent_id を省略した場合はルートに作成する
+#[tauri::command]
+pub fn create_bookmark_folder(
+    name: String,
+    parent_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<BookmarkFolder, String> {
+    let name = validate_folder_name(&name)?;
+
+    let folder = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmark folders")?;
+        FolderTree::ensure_exists(&data.bookmark_folders, parent_id.as_deref())?;
+        FolderTree::ensure_unique_name(&data.bookmark_folders, parent_id.as_deref(), &name, None)?;
+
+        let folder = BookmarkFolder::new(name, parent_id);
+        data.bookmark_folders.push(folder.clone());
+        data.touch();
+        folder
+    };
+
+    log::info!("ブックマークフォルダを作成: {}", folder.name);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(folder)
+}
+
+#[tauri::command]
+pub fn rename_bookmark_folder(
+    folder_id: String,
+    name: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<BookmarkFolder, String> {
+    let name = validate_folder_name(&name)?;
+
+    let folder = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmark folders")?;
+        let parent_id = FolderTree::find(&data.bookmark_folders, &folder_id).ok_or("Folder not found")?.parent_id.clone();
+        FolderTree::ensure_unique_name(&data.bookmark_folders, parent_id.as_deref(), &name, Some(&folder_id))?;
+
+        data.touch();
+        let folder = data
+            .bookmark_folders
+            .iter_mut()
+            .find(|folder| folder.id == folder_id)
+            .ok_or("Folder not found")?;
+        folder.name = name;
+        folder.clone()
+    };
+
+    log::info!("ブックマークフォルダの名前を変更: {} -> {}", folder_id, folder.name);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(folder)
+}
+
+// フォルダを別のフォルダの下へ移動する（parent_id を省略した場合はルートへ）
+#[tauri::command]
+pub fn move_bookmark_folder(
+    folder_id: String,
+    parent_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<BookmarkFolder, String> {
+    let folder = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmark folders")?;
+        let name = FolderTree::find(&data.bookmark_folders, &folder_id).ok_or("Folder not found")?.name.clone();
+        FolderTree::ensure_exists(&data.bookmark_folders, parent_id.as_deref())?;
+        FolderTree::ensure_movable(&data.bookmark_folders, &folder_id, parent_id.as_deref())?;
+        FolderTree::ensure_unique_name(&data.bookmark_folders, parent_id.as_deref(), &name, Some(&folder_id))?;
+
+        data.touch();
+        let folder = data
+            .bookmark_folders
+            .iter_mut()
+            .find(|folder| folder.id == folder_id)
+            .ok_or("Folder not found")?;
+        folder.parent_id = parent_id;
+        folder.clone()
+    };
+
+    log::info!("ブックマークフォルダを移動: {} -> {:?}", folder_id, folder.parent_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(folder)
+}
+
+// フォルダを削除する（mode: "cascade" は中身ごと削除、"reparent" は中身を親フォルダへ移動）
+#[tauri::command]
+pub fn delete_bookmark_folder(
+    folder_id: String,
+    mode: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let mode = DeleteMode::parse(&mode)?;
+
+    let (folders_removed, bookmarks_removed) = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmark folders")?;
+        let removed = FolderTree::delete(&mut data, &folder_id, mode)?;
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.prune(&data);
+        }
+        removed
+    };
+
+    log::info!(
+        "ブックマークフォルダを削除: {} (フォルダ{}件、ブックマーク{}件)",
+        folder_id,
+        folders_removed,
+        bookmarks_removed
+    );
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(format!("Deleted {} folders and {} bookmarks", folders_removed, bookmarks_removed))
+}
+
+// ブックマークを指定のフォルダへ移動する（folder_id を省略した場合はルートへ）、移動した件数を返す
+#[tauri::command]
+pub fn move_bookmarks(
+    bookmark_ids: Vec<String>,
+    folder_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<usize, String> {
+    let moved = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access bookmarks")?;
+        FolderTree::ensure_exists(&data.bookmark_folders, folder_id.as_deref())?;
+
+        let mut moved = 0;
+        for bookmark in data.bookmarks.iter_mut().filter(|bookmark| bookmark_ids.contains(&bookmark.id)) {
+            bookmark.folder_id = folder_id.clone();
+            moved += 1;
+        }
+        data.touch();
+        moved
+    };
+
+    log::info!("ブックマークを移動: {}件 -> {:?}", moved, folder_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(moved)
+}
+
+// folder_id を省略した場合はすべてのブックマーク、空文字はルート直下のブックマークを返す
+fn bookmarks_in_folder<'a>(data: &'a AppData, folder_id: Option<&str>) -> Result<Vec<&'a BookmarkItem>, String> {
+    let Some(folder_id) = folder_id else {
+        return Ok(data.bookmarks.iter().collect());
+    };
+    let folder_id = (!folder_id.is_empty()).then_some(folder_id);
+    FolderTree::ensure_exists(&data.bookmark_folders, folder_id)?;
+
+    Ok(data
+        .bookmarks
+        .iter()
+        .filter(|bookmark| bookmark.folder_id.as_deref() == folder_id)
+        .collect())
+}
+
+fn validate_folder_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
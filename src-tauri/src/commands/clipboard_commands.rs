@@ -1,11 +1,81 @@
 use tauri::{AppHandle, State};
-use crate::models::ClipboardItem;
+use chrono::Utc;
+use crate::models::{CaptureState, ClipboardItem, ItemSummary, MonitorStatus, Page, SearchResult};
+use crate::content_classifier::ContentClassifier;
+use crate::dedup::Dedup;
+use crate::pagination::Paginator;
+use crate::query::QueryParser;
+use crate::search::Search;
+use crate::sensitive_detector::SensitiveDetector;
+use crate::sort::{SortMode, Sorter};
 use crate::ClipboardManager;
 
 #[tauri::command]
 pub fn get_clipboard_history(state: State<'_, ClipboardManager>) -> Result<Vec<ClipboardItem>, String> {
     match state.app_data.lock() {
-        Ok(data) => Ok(data.history.clone()),
+        Ok(data) => {
+            let mut history: Vec<ClipboardItem> = data.history.iter().map(|item| item.masked()).collect();
+            // ピン留めを先頭に（それ以外は元の順序を維持）
+            history.sort_by_key(|item| !item.pinned);
+            Ok(history)
+        }
+        Err(_) => Err("Failed to access clipboard history".to_string()),
+    }
+}
+
+// 指定の順序で履歴を返す（sort_by: recent | frequency | frecency | alphabetical | name | size）
+// ピン留めは常に先頭
+#[tauri::command]
+pub fn get_sorted_history(
+    sort_by: String,
+    state: State<'_, ClipboardManager>,
+) -> Result<Vec<ClipboardItem>, String> {
+    let mode = SortMode::parse(&sort_by)?;
+    let data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+
+    let mut history: Vec<&ClipboardItem> = data.history.iter().collect();
+    Sorter::sort(&mut history, mode, Utc::now(), sort_text);
+    Ok(history.into_iter().map(|item| item.masked()).collect())
+}
+
+// 履歴を指定の順序（省略時は recent）で1ページずつ要約として返す
+#[tauri::command]
+pub fn list_history(
+    cursor: Option<String>,
+    limit: Option<usize>,
+    sort_by: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<Page<ItemSummary>, String> {
+    let mode = SortMode::parse(sort_by.as_deref().unwrap_or("recent"))?;
+    let data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+
+    let mut history: Vec<&ClipboardItem> = data.history.iter().collect();
+    Sorter::sort(&mut history, mode, Utc::now(), sort_text);
+    Paginator::page(&history, cursor.as_deref(), limit, |item| &item.id, |item| item.summary())
+}
+
+// 五十音順の比較に使う文字列（機密アイテムは本文で並べない）
+fn sort_text(item: &ClipboardItem) -> &str {
+    match &item.title {
+        Some(title) => title,
+        None if item.is_sensitive() => "",
+        None => &item.content,
+    }
+}
+
+// マスクされていない本文を取得（機密アイテムの貼り付け用）
+#[tauri::command]
+pub fn get_clipboard_item_content(
+    item_id: String,
+    state: State<'_, ClipboardManager>,
+) -> Result<String, String> {
+    match state.app_data.lock() {
+        Ok(data) => data
+            .history
+            .iter()
+            .find(|item| item.id == item_id)
+            .map(|item| item.content.clone())
+            .ok_or_else(|| "Clipboard item not found".to_string()),
         Err(_) => Err("Failed to access clipboard history".to_string()),
     }
 }
@@ -26,72 +96,205 @@ pub fn delete_clipboard_item(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(pos) = data.history.iter().position(|item| item.id == item_id) {
-                data.history.remove(pos);
-                log::info!("クリップボード履歴アイテム削除: {}", item_id);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+        let pos = data
+            .history
+            .iter()
+            .position(|item| item.id == item_id)
+            .ok_or("Clipboard item not found")?;
+        data.history.remove(pos);
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.remove("history", &item_id);
+        }
+    }
 
-                Ok("Clipboard item deleted successfully".to_string())
-            } else {
-                Err("Clipboard item not found".to_string())
+    log::info!("クリップボード履歴アイテム削除: {}", item_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Clipboard item deleted successfully".to_string())
+}
+
+#[tauri::command]
+pub fn pin_clipboard_item(
+    item_id: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    set_item_pinned(&item_id, true, &state, &app_handle)?;
+    Ok("Clipboard item pinned".to_string())
+}
+
+#[tauri::command]
+pub fn unpin_clipboard_item(
+    item_id: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    set_item_pinned(&item_id, false, &state, &app_handle)?;
+    Ok("Clipboard item unpinned".to_string())
+}
+
+fn set_item_pinned(
+    item_id: &str,
+    pinned: bool,
+    state: &State<'_, ClipboardManager>,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+        let item = data
+            .history
+            .iter_mut()
+            .find(|item| item.id == item_id)
+            .ok_or("Clipboard item not found")?;
+        item.pinned = pinned;
+        data.touch();
+    }
+
+    log::info!("ピン留め{}: {}", if pinned { "設定" } else { "解除" }, item_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(())
+}
+
+// 履歴アイテムのタイトル・メモ・タグ・本文を編集（空文字のタイトル・メモは削除扱い）
+#[tauri::command]
+pub fn update_clipboard_item(
+    item_id: String,
+    title: Option<String>,
+    note: Option<String>,
+    tags: Option<Vec<String>>,
+    content: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<ClipboardItem, String> {
+    let updated = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+        let detect_sensitive = data.settings.sensitive.enabled;
+        let incognito = data.capture_state.incognito;
+        let item = data
+            .history
+            .iter_mut()
+            .find(|item| item.id == item_id)
+            .ok_or("Clipboard item not found")?;
+        // シークレットモード中は保存済みのアイテムを変更しない（取り込み時と同じ扱い）
+        if incognito && !item.incognito {
+            return Err("Cannot edit saved items while incognito mode is on".to_string());
+        }
+
+        if let Some(title) = title {
+            let title = title.trim();
+            item.title = (!title.is_empty()).then(|| title.to_string());
+        }
+        if let Some(note) = note {
+            item.note = (!note.trim().is_empty()).then_some(note);
+        }
+        if let Some(tags) = tags {
+            let mut normalized: Vec<String> = Vec::new();
+            for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+                if !normalized.iter().any(|existing| existing == tag) {
+                    normalized.push(tag.to_string());
+                }
             }
+            item.tags = normalized;
         }
-        Err(_) => Err("Failed to access clipboard history".to_string()),
+        if let Some(content) = content {
+            if content.trim().is_empty() {
+                return Err("Content cannot be empty".to_string());
+            }
+            // 編集後の内容で種類と機密情報を判定し直す
+            item.sensitive_kind = if detect_sensitive {
+                SensitiveDetector::detect(&content).map(|kind| kind.to_string())
+            } else {
+                None
+            };
+            item.content_type = ContentClassifier::classify(&content).to_string();
+            item.size = content.len();
+            item.content = content;
+        }
+
+        if let Ok(mut index) = state.search_index.lock() {
+            index.upsert(&*item);
+        }
+        let updated = item.masked();
+        data.touch();
+        updated
+    };
+
+    log::info!("クリップボードアイテムを更新: {}", item_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
     }
+
+    Ok(updated)
 }
 
+// 履歴をクリア（force指定時はピン留めも削除）
 #[tauri::command]
 pub fn clear_clipboard_history(
+    force: Option<bool>,
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let count = data.history.len();
-            data.history.clear();
-            log::info!("クリップボード履歴をクリア: {} items", count);
-
-            // 自動保存
-            if let Err(e) = state.save_to_file(&app_handle) {
-                log::warn!("自動保存エラー: {}", e);
-            }
+    let force = force.unwrap_or(false);
 
-            Ok(format!("Cleared {} clipboard items", count))
+    let (count, kept) = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+        let original_count = data.history.len();
+        data.history.retain(|item| item.pinned && !force);
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.prune(&data);
         }
-        Err(_) => Err("Failed to access clipboard history".to_string()),
+        (original_count - data.history.len(), data.history.len())
+    };
+    log::info!("クリップボード履歴をクリア: {} items (ピン留め{}件を保持)", count, kept);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
     }
+
+    Ok(format!("Cleared {} clipboard items", count))
 }
 
+// 検索クエリ（あいまい一致・条件指定）で検索し、一致範囲を返す
+// タイトル・タグは本文より高く評価する
 #[tauri::command]
 pub fn search_clipboard_history(
     query: String,
     state: State<'_, ClipboardManager>,
-) -> Result<Vec<ClipboardItem>, String> {
+) -> Result<Vec<SearchResult<ClipboardItem>>, String> {
+    let parsed = QueryParser::parse(&query).map_err(|e| e.to_string())?;
+
     match state.app_data.lock() {
         Ok(data) => {
-            if query.trim().is_empty() {
-                return Ok(data.history.clone());
+            if parsed.is_empty() {
+                return Ok(data
+                    .history
+                    .iter()
+                    .map(|item| SearchResult { item: item.masked(), score: 0.0, matches: Vec::new() })
+                    .collect());
             }
 
-            let query_lower = query.to_lowercase();
-            let mut results: Vec<ClipboardItem> = data
-                .history
-                .iter()
-                .filter(|item| {
-                    item.content.to_lowercase().contains(&query_lower)
-                        || item.content_type.to_lowercase().contains(&query_lower)
-                })
-                .cloned()
+            let index = state.synced_search_index(&data)?;
+            let candidates = index.candidates(&parsed, "history");
+            let results: Vec<SearchResult<ClipboardItem>> = Search::rank(&data.history, &parsed, candidates.as_ref(), &data.settings.search)
+                .into_iter()
+                .map(|(item, score, matches)| SearchResult { item: item.masked(), score, matches })
                 .collect();
-
-            // 最新順でソート
-            results.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
             
             log::info!("クリップボード検索: '{}' -> {} 件", query, results.len());
             Ok(results)
@@ -127,39 +330,125 @@ pub fn stop_clipboard_monitoring(state: State<'_, ClipboardManager>) -> Result<S
     Ok("Clipboard monitoring stopped".to_string())
 }
 
+#[tauri::command]
+pub fn get_monitor_status(state: State<'_, ClipboardManager>) -> Result<MonitorStatus, String> {
+    state.get_monitor_status()
+}
+
+#[tauri::command]
+pub fn start_clipboard_monitoring(
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    state.start_monitoring(app_handle)?;
+    Ok("Clipboard monitoring started".to_string())
+}
+
+// 監視の一時停止（minutes指定時はその時間だけ停止）
+#[tauri::command]
+pub fn pause_clipboard_monitoring(
+    minutes: Option<u64>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<CaptureState, String> {
+    let paused_until = minutes.map(|m| Utc::now() + chrono::Duration::minutes(m as i64));
+
+    let capture_state = state.update_capture_state(&app_handle, |capture_state| {
+        capture_state.paused = true;
+        capture_state.paused_until = paused_until;
+    })?;
+
+    match minutes {
+        Some(m) => log::info!("クリップボード監視を{}分間一時停止", m),
+        None => log::info!("クリップボード監視を一時停止"),
+    }
+    Ok(capture_state)
+}
+
+#[tauri::command]
+pub fn resume_clipboard_monitoring(
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<CaptureState, String> {
+    let capture_state = state.update_capture_state(&app_handle, |capture_state| {
+        capture_state.paused = false;
+        capture_state.paused_until = None;
+    })?;
+
+    log::info!("クリップボード監視を再開");
+    Ok(capture_state)
+}
+
+// シークレットモード: 取り込んだアイテムをディスクに保存しない
+#[tauri::command]
+pub fn set_incognito_mode(
+    enabled: bool,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<CaptureState, String> {
+    let capture_state = state.update_capture_state(&app_handle, |capture_state| {
+        capture_state.incognito = enabled;
+    })?;
+
+    log::info!("シークレットモード: {}", enabled);
+    Ok(capture_state)
+}
+
+#[tauri::command]
+pub fn get_capture_state(state: State<'_, ClipboardManager>) -> Result<CaptureState, String> {
+    match state.app_data.lock() {
+        Ok(data) => Ok(data.capture_state.clone()),
+        Err(_) => Err("Failed to access capture state".to_string()),
+    }
+}
+
+// 正規化・類似度による重複グループを検出
+// similarity_threshold を省略した場合は設定値を使用
 #[tauri::command]
 pub fn find_duplicate_clipboard_items(
+    similarity_threshold: Option<f64>,
     state: State<'_, ClipboardManager>,
 ) -> Result<serde_json::Value, String> {
-    use std::collections::HashMap;
-    
     match state.app_data.lock() {
         Ok(data) => {
-            let mut content_map: HashMap<String, Vec<&ClipboardItem>> = HashMap::new();
-            
-            // コンテンツ別にグループ化
-            for item in &data.history {
-                content_map.entry(item.content.clone()).or_default().push(item);
-            }
-            
-            // 重複があるグループのみ抽出
-            let duplicates: HashMap<String, Vec<&ClipboardItem>> = content_map
-                .into_iter()
-                .filter(|(_, items)| items.len() > 1)
+            let settings = &data.settings.dedup;
+            let threshold = similarity_threshold.unwrap_or(settings.similarity_threshold);
+
+            // 機密アイテムはプレビューに出さないため除外
+            let candidates: Vec<ClipboardItem> = data
+                .history
+                .iter()
+                .filter(|item| !item.is_sensitive())
+                .cloned()
                 .collect();
+            let groups = Dedup::group_indices(&candidates, settings, threshold);
             
-            log::info!("重複クリップボードアイテム検出: {} グループ", duplicates.len());
+            log::info!("重複クリップボードアイテム検出: {} グループ", groups.len());
             
-            let duplicate_info: Vec<serde_json::Value> = duplicates
+            let duplicate_info: Vec<serde_json::Value> = groups
                 .iter()
-                .map(|(content, items)| {
+                .map(|group| {
+                    let items: Vec<&ClipboardItem> = group.iter().map(|&index| &candidates[index]).collect();
+                    let first = &items[0].content;
+                    let exact = items.iter().all(|item| &item.content == first);
+                    let min_similarity = items
+                        .iter()
+                        .skip(1)
+                        .map(|item| Dedup::similarity(
+                            &Dedup::normalize(first, settings),
+                            &Dedup::normalize(&item.content, settings),
+                        ))
+                        .fold(1.0_f64, f64::min);
+
                     serde_json::json!({
-                        "content_preview": if content.len() > 50 { 
-                            format!("{}...", &content[..50]) 
-                        } else { 
-                            content.clone() 
+                        "content_preview": if first.chars().count() > 50 {
+                            format!("{}...", first.chars().take(50).collect::<String>())
+                        } else {
+                            first.clone()
                         },
                         "count": items.len(),
+                        "match": if exact { "exact" } else { "similar" },
+                        "similarity": min_similarity,
                         "item_ids": items.iter().map(|item| &item.id).collect::<Vec<_>>(),
                         "timestamps": items.iter().map(|item| &item.timestamp).collect::<Vec<_>>()
                     })
@@ -168,9 +457,66 @@ pub fn find_duplicate_clipboard_items(
             
             Ok(serde_json::json!({
                 "duplicate_groups": duplicate_info,
-                "total_duplicates": duplicates.len()
+                "total_duplicates": groups.len()
             }))
         }
         Err(_) => Err("Failed to access clipboard data".to_string()),
     }
-}
\ No newline at end of file
+}
+
+// 複数アイテムを1件に統合（canonical_id省略時は最新のアイテムを残す）
+#[tauri::command]
+pub fn merge_clipboard_items(
+    item_ids: Vec<String>,
+    canonical_id: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<ClipboardItem, String> {
+    let merged = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access clipboard history")?;
+
+        let selected: Vec<ClipboardItem> = data
+            .history
+            .iter()
+            .filter(|item| item_ids.contains(&item.id))
+            .cloned()
+            .collect();
+        if selected.len() < 2 {
+            return Err("At least two existing items are required to merge".to_string());
+        }
+
+        let canonical_id = match canonical_id {
+            Some(id) if selected.iter().any(|item| item.id == id) => id,
+            Some(_) => return Err("Canonical item must be one of the merged items".to_string()),
+            None => selected.iter().max_by_key(|item| item.timestamp).map(|item| item.id.clone()).unwrap_or_default(),
+        };
+
+        let others: Vec<ClipboardItem> = selected.into_iter().filter(|item| item.id != canonical_id).collect();
+        data.history.retain(|item| !others.iter().any(|other| other.id == item.id));
+
+        let canonical = data
+            .history
+            .iter_mut()
+            .find(|item| item.id == canonical_id)
+            .ok_or("Clipboard item not found")?;
+        Dedup::merge_into(canonical, &others);
+        let merged = canonical.masked();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.upsert(&*canonical);
+            for other in &others {
+                index.remove("history", &other.id);
+            }
+        }
+        data.touch();
+
+        log::info!("クリップボードアイテムを統合: {} 件 -> {}", others.len() + 1, canonical_id);
+        merged
+    };
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(merged)
+}
@@ -1,6 +1,8 @@
 use tauri::{AppHandle, State};
 use regex::Regex;
-use crate::models::IpHistoryItem;
+use crate::models::{IpHistoryItem, SearchResult};
+use crate::query::QueryParser;
+use crate::search::Search;
 use crate::ClipboardManager;
 
 // IP関数をlib.rsから移動
@@ -74,24 +76,27 @@ pub fn remove_ip_from_recent(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(pos) = data.recent_ips.iter().position(|item| item.ip == ip) {
-                data.recent_ips.remove(pos);
-                log::info!("IP履歴から削除: {}", ip);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-
-                Ok(format!("IP {} removed from history", ip))
-            } else {
-                Err("IP not found in history".to_string())
-            }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access IP history")?;
+        let pos = data
+            .recent_ips
+            .iter()
+            .position(|item| item.ip == ip)
+            .ok_or("IP not found in history")?;
+        data.recent_ips.remove(pos);
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.remove("ip", &ip);
         }
-        Err(_) => Err("Failed to access IP history".to_string()),
     }
+    log::info!("IP履歴から削除: {}", ip);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(format!("IP {} removed from history", ip))
 }
 
 #[tauri::command]
@@ -105,39 +110,49 @@ pub fn clear_ip_history(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            let count = data.recent_ips.len();
-            data.recent_ips.clear();
-            log::info!("IP履歴をクリア: {} items", count);
-
-            // 自動保存
-            if let Err(e) = state.save_to_file(&app_handle) {
-                log::warn!("自動保存エラー: {}", e);
-            }
-
-            Ok(format!("Cleared {} IP entries", count))
+    let count = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access IP history")?;
+        let count = data.recent_ips.len();
+        data.recent_ips.clear();
+        data.touch();
+        if let Ok(mut index) = state.search_index.lock() {
+            index.prune(&data);
         }
-        Err(_) => Err("Failed to access IP history".to_string()),
+        count
+    };
+    log::info!("IP履歴をクリア: {} items", count);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
     }
+
+    Ok(format!("Cleared {} IP entries", count))
 }
 
+// 検索クエリ（あいまい一致・条件指定）で検索し、一致範囲を返す
 #[tauri::command]
 pub fn search_ip_history(
     query: String,
     state: State<'_, ClipboardManager>,
-) -> Result<Vec<IpHistoryItem>, String> {
+) -> Result<Vec<SearchResult<IpHistoryItem>>, String> {
+    let parsed = QueryParser::parse(&query).map_err(|e| e.to_string())?;
+
     match state.app_data.lock() {
         Ok(data) => {
-            if query.trim().is_empty() {
-                return Ok(data.recent_ips.clone());
+            if parsed.is_empty() {
+                return Ok(data
+                    .recent_ips
+                    .iter()
+                    .map(|item| SearchResult { item: item.clone(), score: 0.0, matches: Vec::new() })
+                    .collect());
             }
 
-            let results: Vec<IpHistoryItem> = data
-                .recent_ips
-                .iter()
-                .filter(|item| item.ip.contains(&query))
-                .cloned()
+            let index = state.synced_search_index(&data)?;
+            let candidates = index.candidates(&parsed, "ip");
+            let results: Vec<SearchResult<IpHistoryItem>> = Search::rank(&data.recent_ips, &parsed, candidates.as_ref(), &data.settings.search)
+                .into_iter()
+                .map(|(item, score, matches)| SearchResult { item: item.clone(), score, matches })
                 .collect();
 
             log::info!("IP履歴検索: '{}' -> {} 件", query, results.len());
@@ -153,23 +168,23 @@ pub fn reset_ip_count(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            if let Some(ip_item) = data.recent_ips.iter_mut().find(|item| item.ip == ip) {
-                ip_item.count = 1;
-                log::info!("IPカウントをリセット: {}", ip);
-
-                // 自動保存
-                if let Err(e) = state.save_to_file(&app_handle) {
-                    log::warn!("自動保存エラー: {}", e);
-                }
-
-                Ok(format!("Reset count for IP {}", ip))
-            } else {
-                Err("IP not found in history".to_string())
-            }
-        }
-        Err(_) => Err("Failed to access IP history".to_string()),
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access IP history")?;
+        let ip_item = data
+            .recent_ips
+            .iter_mut()
+            .find(|item| item.ip == ip)
+            .ok_or("IP not found in history")?;
+        ip_item.count = 1;
+        data.touch();
     }
+    log::info!("IPカウントをリセット: {}", ip);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(format!("Reset count for IP {}", ip))
 }
 
@@ -3,10 +3,14 @@ pub mod bookmark_commands;
 pub mod ip_commands;
 pub mod settings_commands;
 pub mod app_commands;
+pub mod search_commands;
+pub mod saved_search_commands;
 
 // すべてのコマンドを再エクスポート
 pub use clipboard_commands::*;
 pub use bookmark_commands::*;
 pub use ip_commands::*;
 pub use settings_commands::*;
-pub use app_commands::*;
\ No newline at end of file
+pub use app_commands::*;
+pub use search_commands::*;
+pub use saved_search_commands::*;
\ No newline at end of file
@@ -0,0 +1,187 @@
+use tauri::{AppHandle, State};
+use chrono::Utc;
+use crate::models::{SavedSearch, SavedSearchCount, SearchAllResponse, SearchLimits};
+use crate::query::QueryParser;
+use crate::commands::search_commands::{count_data, search_data};
+use crate::ClipboardManager;
+
+#[tauri::command]
+pub fn get_saved_searches(state: State<'_, ClipboardManager>) -> Result<Vec<SavedSearch>, String> {
+    match state.app_data.lock() {
+        Ok(data) => Ok(data.saved_searches.clone()),
+        Err(_) => Err("Failed to access saved searches".to_string()),
+    }
+}
+
+// 検索クエリに名前を付けて保存する（クエリは保存時に構文を検証する）
+#[tauri::command]
+pub fn create_saved_search(
+    name: String,
+    query: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<SavedSearch, String> {
+    let name = validate_name(&name)?;
+    let query = validate_query(&query)?;
+
+    let saved_search = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access saved searches")?;
+        if data.saved_searches.iter().any(|existing| existing.name == name) {
+            return Err(format!("A saved search named '{}' already exists", name));
+        }
+        let saved_search = SavedSearch::new(name, query);
+        data.saved_searches.push(saved_search.clone());
+        data.touch();
+        saved_search
+    };
+
+    log::info!("検索を保存: {} ({})", saved_search.name, saved_search.query);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(saved_search)
+}
+
+#[tauri::command]
+pub fn update_saved_search(
+    search_id: String,
+    name: Option<String>,
+    query: Option<String>,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<SavedSearch, String> {
+    let name = name.as_deref().map(validate_name).transpose()?;
+    let query = query.as_deref().map(validate_query).transpose()?;
+
+    let updated = {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access saved searches")?;
+        if let Some(name) = &name {
+            if data.saved_searches.iter().any(|existing| existing.name == *name && existing.id != search_id) {
+                return Err(format!("A saved search named '{}' already exists", name));
+            }
+        }
+
+        let saved_search = data
+            .saved_searches
+            .iter_mut()
+            .find(|saved_search| saved_search.id == search_id)
+            .ok_or("Saved search not found")?;
+        if let Some(name) = name {
+            saved_search.name = name;
+        }
+        if let Some(query) = query {
+            saved_search.query = query;
+        }
+        saved_search.updated_at = Utc::now();
+        let updated = saved_search.clone();
+        data.touch();
+        updated
+    };
+
+    log::info!("保存した検索を更新: {}", search_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_saved_search(
+    search_id: String,
+    state: State<'_, ClipboardManager>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access saved searches")?;
+        let pos = data
+            .saved_searches
+            .iter()
+            .position(|saved_search| saved_search.id == search_id)
+            .ok_or("Saved search not found")?;
+        data.saved_searches.remove(pos);
+        data.touch();
+    }
+
+    log::info!("保存した検索を削除: {}", search_id);
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Saved search deleted successfully".to_string())
+}
+
+// 保存した検索を現在の履歴・ブックマーク・IP履歴に対して評価する（並び順は search_all と同じ）
+#[tauri::command]
+pub fn run_saved_search(
+    search_id: String,
+    limits: Option<SearchLimits>,
+    state: State<'_, ClipboardManager>,
+) -> Result<SearchAllResponse, String> {
+    let limits = limits.unwrap_or_default();
+    let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+
+    let saved_search = data
+        .saved_searches
+        .iter()
+        .find(|saved_search| saved_search.id == search_id)
+        .ok_or("Saved search not found")?;
+    let parsed = QueryParser::parse(&saved_search.query).map_err(|e| e.to_string())?;
+    let index = state.synced_search_index(&data)?;
+
+    Ok(search_data(&data, &index, &parsed, &limits))
+}
+
+// 保存したすべての検索の一致件数を返す（サイドバーのバッジ用）
+// クエリが不正になったもの（手動編集など）は0件として扱う
+#[tauri::command]
+pub fn get_saved_search_counts(state: State<'_, ClipboardManager>) -> Result<Vec<SavedSearchCount>, String> {
+    let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+    let index = state.synced_search_index(&data)?;
+
+    let counts = data
+        .saved_searches
+        .iter()
+        .map(|saved_search| {
+            let (total_history, total_bookmarks, total_ips) = match QueryParser::parse(&saved_search.query) {
+                Ok(parsed) => count_data(&data, &index, &parsed),
+                Err(e) => {
+                    log::warn!("保存した検索のクエリが不正です: {} ({})", saved_search.name, e);
+                    (0, 0, 0)
+                }
+            };
+            SavedSearchCount {
+                id: saved_search.id.clone(),
+                total: total_history + total_bookmarks + total_ips,
+                total_history,
+                total_bookmarks,
+                total_ips,
+            }
+        })
+        .collect();
+
+    Ok(counts)
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn validate_query(query: &str) -> Result<String, String> {
+    let query = query.trim();
+    if QueryParser::parse(query).map_err(|e| e.to_string())?.is_empty() {
+        return Err("Query cannot be empty".to_string());
+    }
+    Ok(query.to_string())
+}
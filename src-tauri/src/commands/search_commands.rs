@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+use tauri::State;
+use crate::models::{AppData, FieldMatch, RegexSearchResponse, SearchAllResponse, SearchHit, SearchLimits, SearchResult};
+use crate::query::{Query, QueryParser};
+use crate::regex_search::RegexSearch;
+use crate::search::Search;
+use crate::search_index::SearchIndex;
+use crate::ClipboardManager;
+
+// 正規表現検索で種類ごとに返す最大件数（省略時）
+const DEFAULT_REGEX_LIMIT: usize = 100;
+
+// 履歴・ブックマーク・IP履歴を横断検索し、種類付きの結果をスコア順に返す
+// 各種類の並び順と一致判定は個別の検索コマンドと同じ
+#[tauri::command]
+pub fn search_all(
+    query: String,
+    limits: Option<SearchLimits>,
+    state: State<'_, ClipboardManager>,
+) -> Result<SearchAllResponse, String> {
+    let parsed = QueryParser::parse(&query).map_err(|e| e.to_string())?;
+    let limits = limits.unwrap_or_default();
+
+    let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+    let index = state.synced_search_index(&data)?;
+    let response = search_data(&data, &index, &parsed, &limits);
+
+    log::info!(
+        "横断検索: '{}' -> 履歴 {} 件, ブックマーク {} 件, IP {} 件",
+        query,
+        response.total_history,
+        response.total_bookmarks,
+        response.total_ips
+    );
+    Ok(response)
+}
+
+// 解析済みのクエリで横断検索する（保存した検索の評価でも使う）
+pub(crate) fn search_data(data: &AppData, index: &SearchIndex, parsed: &Query, limits: &SearchLimits) -> SearchAllResponse {
+    let settings = &data.settings.search;
+
+    let history = Search::rank(&data.history, parsed, index.candidates(parsed, "history").as_ref(), settings);
+    let bookmarks = Search::rank(&data.bookmarks, parsed, index.candidates(parsed, "bookmark").as_ref(), settings);
+    let ips = Search::rank(&data.recent_ips, parsed, index.candidates(parsed, "ip").as_ref(), settings);
+    let (total_history, total_bookmarks, total_ips) = (history.len(), bookmarks.len(), ips.len());
+
+    let mut results: Vec<SearchHit> = history
+        .into_iter()
+        .take(limits.history)
+        .map(|(item, score, matches)| SearchHit::History(SearchResult { item: item.masked(), score, matches }))
+        .chain(bookmarks.into_iter().take(limits.bookmarks).map(|(item, score, matches)| {
+            SearchHit::Bookmark(SearchResult { item: item.clone(), score, matches })
+        }))
+        .chain(ips.into_iter().take(limits.ips).map(|(item, score, matches)| {
+            SearchHit::Ip(SearchResult { item: item.clone(), score, matches })
+        }))
+        .collect();
+
+    // 種類をまたいでスコア順に並べる（同点は各種類内の順序を維持）
+    results.sort_by(|a, b| b.score().total_cmp(&a.score()));
+
+    SearchAllResponse {
+        results,
+        total_history,
+        total_bookmarks,
+        total_ips,
+    }
+}
+
+// 解析済みのクエリに一致する件数を種類ごとに返す（履歴, ブックマーク, IP）
+// インデックスで絞り込んだ候補に条件を当てるだけで、並べ替えや結果の作成は行わない
+pub(crate) fn count_data(data: &AppData, index: &SearchIndex, parsed: &Query) -> (usize, usize, usize) {
+    let settings = &data.settings.search;
+    (
+        Search::count(&data.history, parsed, index.candidates(parsed, "history").as_ref(), settings),
+        Search::count(&data.bookmarks, parsed, index.candidates(parsed, "bookmark").as_ref(), settings),
+        Search::count(&data.recent_ips, parsed, index.candidates(parsed, "ip").as_ref(), settings),
+    )
+}
+
+// 正規表現で履歴・ブックマークを検索し、一致範囲を返す（scope: "history" | "bookmarks" | "all"）
+// UIスレッドや監視スレッドを止めないよう、データのスナップショットに対して別スレッドで検索する
+#[tauri::command]
+pub async fn search_regex(
+    pattern: String,
+    case_insensitive: Option<bool>,
+    scope: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, ClipboardManager>,
+) -> Result<RegexSearchResponse, String> {
+    let mut search = RegexSearch::compile(&pattern, case_insensitive.unwrap_or(false))?;
+    let (include_history, include_bookmarks) = match scope.as_deref().unwrap_or("all") {
+        "all" => (true, true),
+        "history" => (true, false),
+        "bookmarks" => (false, true),
+        other => return Err(format!("Unknown search scope: {}", other)),
+    };
+    let limit = limit.unwrap_or(DEFAULT_REGEX_LIMIT);
+
+    let (mut history, bookmarks) = {
+        let data = state.app_data.lock().map_err(|_| "Failed to access app data")?;
+        (
+            if include_history { data.history.clone() } else { Vec::new() },
+            if include_bookmarks { data.bookmarks.clone() } else { Vec::new() },
+        )
+    };
+    // 時間切れの場合も新しいものから結果が返るよう新しい順に検索する
+    history.sort_by_key(|item| Reverse(item.timestamp));
+
+    let response = tokio::task::spawn_blocking(move || {
+        search.start();
+        let history_matches = search.find_all(&history);
+        let bookmark_matches = search.find_all(&bookmarks);
+
+        RegexSearchResponse {
+            total_history: history_matches.len(),
+            total_bookmarks: bookmark_matches.len(),
+            history: history_matches
+                .into_iter()
+                .take(limit)
+                .map(|(item, matches)| SearchResult { item: item.masked(), score: span_count(&matches), matches })
+                .collect(),
+            bookmarks: bookmark_matches
+                .into_iter()
+                .take(limit)
+                .map(|(item, matches)| SearchResult { item: item.clone(), score: span_count(&matches), matches })
+                .collect(),
+            timed_out: search.timed_out(),
+        }
+    })
+    .await
+    .map_err(|e| format!("Regex search failed: {}", e))?;
+
+    log::info!(
+        "正規表現検索: '{}' -> 履歴 {} 件, ブックマーク {} 件{}",
+        pattern,
+        response.total_history,
+        response.total_bookmarks,
+        if response.timed_out { "（時間切れ）" } else { "" }
+    );
+    Ok(response)
+}
+
+// 検索欄に入力中のパターンを検証する（エラー時はその理由を返す）
+#[tauri::command]
+pub fn validate_regex_pattern(pattern: String) -> Result<(), String> {
+    RegexSearch::compile(&pattern, false).map(|_| ())
+}
+
+fn span_count(matches: &[FieldMatch]) -> f64 {
+    matches.iter().map(|field_match| field_match.ranges.len()).sum::<usize>() as f64
+}
\ No newline at end of file
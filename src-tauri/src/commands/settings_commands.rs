@@ -1,5 +1,8 @@
 use tauri::{AppHandle, State};
-use crate::models::AppSettings;
+use crate::models::{AppSettings, CaptureRule};
+use crate::eviction::Eviction;
+use crate::capture_rules::{CaptureInput, RuleEngine, RuleOutcome};
+use crate::content_classifier::ContentClassifier;
 use crate::ClipboardManager;
 
 #[tauri::command]
@@ -16,18 +19,59 @@ pub fn update_settings(
     state: State<'_, ClipboardManager>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    match state.app_data.lock() {
-        Ok(mut data) => {
-            data.settings = new_settings;
-            log::info!("設定を更新しました");
+    RuleEngine::validate(&new_settings.capture_rules)?;
 
-            // 自動保存
-            if let Err(e) = state.save_to_file(&app_handle) {
-                log::warn!("自動保存エラー: {}", e);
-            }
+    {
+        let mut data = state.app_data.lock().map_err(|_| "Failed to access settings")?;
+        data.settings = new_settings;
 
-            Ok("Settings updated successfully".to_string())
+        // 変更後の退避ポリシーを既存の履歴にも適用
+        Eviction::apply(&mut data, None);
+        data.touch();
+
+        // 正規化の設定が変わった場合は作り直し、退避したアイテムを取り除く
+        if let Ok(mut index) = state.search_index.lock() {
+            if index.is_stale(&data.settings.search) {
+                index.rebuild(&data);
+            } else {
+                index.prune(&data);
+            }
         }
-        Err(_) => Err("Failed to access settings".to_string()),
     }
-}
\ No newline at end of file
+    log::info!("設定を更新しました");
+
+    // 自動保存
+    if let Err(e) = state.save_to_file(&app_handle) {
+        log::warn!("自動保存エラー: {}", e);
+    }
+
+    Ok("Settings updated successfully".to_string())
+}
+
+// サンプルテキストに対してどのルールが発動するかを確認
+// rulesを省略した場合は保存済みのルールで評価する
+#[tauri::command]
+pub fn test_capture_rules(
+    text: String,
+    rules: Option<Vec<CaptureRule>>,
+    content_type: Option<String>,
+    source_app: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<RuleOutcome, String> {
+    let rules = match rules {
+        Some(rules) => rules,
+        None => match state.app_data.lock() {
+            Ok(data) => data.settings.capture_rules.clone(),
+            Err(_) => return Err("Failed to access settings".to_string()),
+        },
+    };
+
+    let outcome = RuleEngine::evaluate(&rules, &CaptureInput {
+        text: &text,
+        content_type: content_type.as_deref().unwrap_or_else(|| ContentClassifier::classify(&text)),
+        source_app: source_app.as_deref(),
+    });
+
+    log::info!("キャプチャルールテスト: {} 件マッチ", outcome.matched_rules.len());
+    Ok(outcome)
+}
@@ -0,0 +1,50 @@
+use std::sync::OnceLock;
+use regex::Regex;
+
+static STACK_FRAME: OnceLock<Regex> = OnceLock::new();
+
+pub struct ContentClassifier;
+
+impl ContentClassifier {
+    // キャプチャしたテキストの種類を判定（"url" | "json" | "stack_trace" | "text"）
+    pub fn classify(text: &str) -> &'static str {
+        let trimmed = text.trim();
+
+        if Self::is_url(trimmed) {
+            return "url";
+        }
+
+        if Self::is_json(trimmed) {
+            return "json";
+        }
+
+        if Self::is_stack_trace(trimmed) {
+            return "stack_trace";
+        }
+
+        "text"
+    }
+
+    fn is_url(text: &str) -> bool {
+        (text.starts_with("http://") || text.starts_with("https://"))
+            && !text.chars().any(char::is_whitespace)
+    }
+
+    fn is_json(text: &str) -> bool {
+        let looks_like_json = (text.starts_with('{') && text.ends_with('}'))
+            || (text.starts_with('[') && text.ends_with(']'));
+        looks_like_json && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    }
+
+    // Python/Java/JavaScript/Rustのスタックトレースを検出
+    fn is_stack_trace(text: &str) -> bool {
+        if text.contains("Traceback (most recent call last):") || text.contains("stack backtrace:") {
+            return true;
+        }
+
+        let stack_frame = STACK_FRAME.get_or_init(|| {
+            Regex::new(r#"^\s+(?:at \S+|File ".+", line \d+|\d+: \S+::)"#).unwrap()
+        });
+        text.lines().filter(|line| stack_frame.is_match(line)).count() >= 2
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use crate::models::{push_usage_event, ClipboardItem, DedupSettings};
+
+// 類似度計算の対象にする最大文字数（これより長いものは正規化後の一致のみ判定）
+const MAX_SIMILARITY_CHARS: usize = 20_000;
+
+pub struct Dedup;
+
+impl Dedup {
+    // 比較用の正規化キー
+    pub fn normalize(text: &str, settings: &DedupSettings) -> String {
+        let mut normalized = if settings.ignore_whitespace {
+            // 行ごとのインデント・末尾空白と空行の違いを無視
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            text.to_string()
+        };
+
+        if settings.ignore_case {
+            normalized = normalized.to_lowercase();
+        }
+
+        normalized
+    }
+
+    // 文字バイグラムのDice係数（0.0〜1.0）
+    pub fn similarity(a: &str, b: &str) -> f64 {
+        if a == b {
+            return 1.0;
+        }
+
+        let bigrams_a = Self::bigrams(a);
+        let bigrams_b = Self::bigrams(b);
+        let total: usize = bigrams_a.values().sum::<usize>() + bigrams_b.values().sum::<usize>();
+        if total == 0 {
+            return 0.0;
+        }
+
+        let shared: usize = bigrams_a
+            .iter()
+            .map(|(bigram, count)| (*count).min(*bigrams_b.get(bigram).unwrap_or(&0)))
+            .sum();
+
+        2.0 * shared as f64 / total as f64
+    }
+
+    fn bigrams(text: &str) -> HashMap<(char, char), usize> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut bigrams = HashMap::new();
+        for pair in chars.windows(2) {
+            *bigrams.entry((pair[0], pair[1])).or_insert(0) += 1;
+        }
+        bigrams
+    }
+
+    // 重複グループ（2件以上）をインデックスで返す
+    // threshold が 1.0 以上の場合は正規化後の一致のみでグループ化する
+    pub fn group_indices(items: &[ClipboardItem], settings: &DedupSettings, threshold: f64) -> Vec<Vec<usize>> {
+        // 正規化キーでバケット化（出現順を維持）
+        let mut keys: Vec<String> = Vec::new();
+        let mut buckets: Vec<Vec<usize>> = Vec::new();
+        let mut key_to_bucket: HashMap<String, usize> = HashMap::new();
+
+        for (index, item) in items.iter().enumerate() {
+            let key = Self::normalize(&item.content, settings);
+            match key_to_bucket.get(&key) {
+                Some(&bucket) => buckets[bucket].push(index),
+                None => {
+                    key_to_bucket.insert(key.clone(), buckets.len());
+                    keys.push(key);
+                    buckets.push(vec![index]);
+                }
+            }
+        }
+
+        // 類似バケット同士を統合（union-find）
+        let mut parent: Vec<usize> = (0..buckets.len()).collect();
+        if threshold < 1.0 {
+            for i in 0..keys.len() {
+                for j in (i + 1)..keys.len() {
+                    if Self::is_similar(&keys[i], &keys[j], threshold) {
+                        let (root_i, root_j) = (Self::find(&mut parent, i), Self::find(&mut parent, j));
+                        if root_i != root_j {
+                            parent[root_j] = root_i;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut root_to_group: HashMap<usize, usize> = HashMap::new();
+        for (bucket_index, bucket) in buckets.into_iter().enumerate() {
+            let root = Self::find(&mut parent, bucket_index);
+            let group = *root_to_group.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].extend(bucket);
+        }
+
+        groups.retain(|group| group.len() > 1);
+        for group in &mut groups {
+            group.sort_unstable();
+        }
+        groups
+    }
+
+    fn is_similar(a: &str, b: &str, threshold: f64) -> bool {
+        let (len_a, len_b) = (a.chars().count(), b.chars().count());
+        if len_a > MAX_SIMILARITY_CHARS || len_b > MAX_SIMILARITY_CHARS {
+            return false;
+        }
+
+        // 長さの比が閾値を下回る場合はDice係数も閾値に届かない
+        let (shorter, longer) = (len_a.min(len_b) as f64, len_a.max(len_b) as f64);
+        if longer > 0.0 && 2.0 * shorter / (shorter + longer) < threshold {
+            return false;
+        }
+
+        Self::similarity(a, b) >= threshold
+    }
+
+    fn find(parent: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[index] = root;
+        root
+    }
+
+    // 内容が完全に一致する履歴を最新のアイテムへ統合し、削除件数を返す（起動時用）
+    // 正規化・類似度による統合はユーザーの操作でのみ行う
+    pub fn dedup_history(history: &mut Vec<ClipboardItem>) -> usize {
+        let exact = DedupSettings {
+            ignore_whitespace: false,
+            ignore_case: false,
+            similarity_threshold: 1.0,
+        };
+        let groups = Self::group_indices(history, &exact, 1.0);
+        let mut removed_indices: Vec<usize> = Vec::new();
+
+        for group in groups {
+            let Some(&canonical_index) = group.iter().max_by_key(|&&index| history[index].timestamp) else {
+                continue;
+            };
+            let others: Vec<ClipboardItem> = group
+                .iter()
+                .filter(|&&index| index != canonical_index)
+                .map(|&index| history[index].clone())
+                .collect();
+
+            Self::merge_into(&mut history[canonical_index], &others);
+            removed_indices.extend(group.into_iter().filter(|&index| index != canonical_index));
+        }
+
+        removed_indices.sort_unstable();
+        for index in removed_indices.iter().rev() {
+            history.remove(*index);
+        }
+        removed_indices.len()
+    }
+
+    // 重複アイテムを代表アイテムに統合する
+    // アクセス回数は合算し、初回取得時刻は最も古いものを残す
+    pub fn merge_into(canonical: &mut ClipboardItem, others: &[ClipboardItem]) {
+        // 利用履歴は時系列に並べ直してから上限件数に切り詰める
+        let mut timeline = std::mem::take(&mut canonical.timeline);
+        timeline.extend(others.iter().flat_map(|other| other.timeline.iter().cloned()));
+        timeline.sort_by_key(|event| event.timestamp);
+        for event in timeline {
+            push_usage_event(&mut canonical.timeline, event);
+        }
+
+        for other in others {
+            canonical.access_count += other.access_count;
+            canonical.copy_count = canonical.copy_count.max(1) + other.copy_count.max(1);
+            canonical.last_copied = canonical.last_copied.max(other.last_copied);
+            canonical.first_seen = Some(canonical.first_seen().min(other.first_seen()));
+            canonical.last_accessed = canonical.last_accessed.max(other.last_accessed);
+            canonical.pinned |= other.pinned;
+            if canonical.title.is_none() {
+                canonical.title = other.title.clone();
+            }
+            if canonical.note.is_none() {
+                canonical.note = other.note.clone();
+            }
+            for tag in &other.tags {
+                if !canonical.tags.contains(tag) {
+                    canonical.tags.push(tag.clone());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use crate::frecency;
+use crate::models::{AppData, ClipboardItem};
+
+pub struct Eviction;
+
+impl Eviction {
+    // 履歴に退避ポリシーを適用し、削除件数を返す
+    // ピン留めと protected_id（直前に追加したアイテム）は削除しない
+    pub fn apply(data: &mut AppData, protected_id: Option<&str>) -> usize {
+        let original_count = data.history.len();
+        let policy = data.settings.eviction.clone();
+        let is_candidate = |item: &ClipboardItem| !item.pinned && Some(item.id.as_str()) != protected_id;
+
+        // 最大保持期間
+        if let Some(max_age_days) = policy.max_age_days {
+            let cutoff = Utc::now() - chrono::Duration::days(max_age_days);
+            data.history.retain(|item| !(is_candidate(item) && item.timestamp < cutoff));
+        }
+
+        // 種類別の件数上限
+        for (content_type, quota) in &policy.type_quotas {
+            while data.history.iter().filter(|item| &item.content_type == content_type).count() > *quota {
+                let Some(pos) = Self::select_victim(&data.history, &policy.strategy, |item| {
+                    is_candidate(item) && &item.content_type == content_type
+                }) else {
+                    break;
+                };
+                data.history.remove(pos);
+            }
+        }
+
+        // 件数上限
+        while data.history.len() > data.settings.history_limit {
+            let Some(pos) = Self::select_victim(&data.history, &policy.strategy, is_candidate) else {
+                break; // すべてピン留めの場合は制限を超えて保持
+            };
+            data.history.remove(pos);
+        }
+
+        // 合計サイズ上限
+        if let Some(max_total_bytes) = policy.max_total_bytes {
+            while data.history.iter().map(|item| item.size).sum::<usize>() > max_total_bytes {
+                let Some(pos) = Self::select_victim(&data.history, &policy.strategy, is_candidate) else {
+                    break;
+                };
+                data.history.remove(pos);
+            }
+        }
+
+        let removed = original_count - data.history.len();
+        if removed > 0 {
+            log::info!("退避ポリシー({}): {} 件を削除", policy.strategy, removed);
+        }
+        removed
+    }
+
+    // 削除対象を選ぶ: "lru" は最終利用が最も古いもの、"frecency" はスコア最小、それ以外は挿入順
+    fn select_victim<F>(history: &[ClipboardItem], strategy: &str, is_candidate: F) -> Option<usize>
+    where
+        F: Fn(&ClipboardItem) -> bool,
+    {
+        let candidates = history.iter().enumerate().filter(|(_, item)| is_candidate(item));
+
+        match strategy {
+            "lru" => candidates
+                .min_by_key(|(_, item)| item.last_accessed.unwrap_or(item.timestamp))
+                .map(|(pos, _)| pos),
+            "frecency" => {
+                let now = Utc::now();
+                candidates
+                    .min_by(|(_, a), (_, b)| {
+                        let score_a = frecency::score(a.access_count, a.last_accessed.unwrap_or(a.timestamp), now);
+                        let score_b = frecency::score(b.access_count, b.last_accessed.unwrap_or(b.timestamp), now);
+                        score_a.total_cmp(&score_b)
+                    })
+                    .map(|(pos, _)| pos)
+            }
+            _ => candidates.map(|(pos, _)| pos).next(),
+        }
+    }
+}
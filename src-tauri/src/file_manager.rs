@@ -34,6 +34,43 @@ impl FileManager {
         Ok(app_data_dir.join("clipboard_manager.log"))
     }
     
+    // セッション記録の保存先ディレクトリ
+    pub fn get_recordings_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+        let recordings_dir = app_handle.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?
+            .join("recordings");
+        
+        if !recordings_dir.exists() {
+            fs::create_dir_all(&recordings_dir)
+                .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+        }
+        
+        Ok(recordings_dir)
+    }
+    
+    // ローテート済みのログファイルの一覧（書き込み中の現在のログは含めない）
+    pub fn list_log_files(app_handle: &AppHandle) -> Result<Vec<PathBuf>, String> {
+        let log_path = Self::get_log_file_path(app_handle)?;
+        let (Some(log_dir), Some(log_name)) = (log_path.parent(), log_path.file_name()) else {
+            return Ok(Vec::new());
+        };
+        // ローテート時は "clipboard_manager.log.old" のように拡張子を付けて退避する
+        let rotated_prefix = format!("{}.", log_name.to_string_lossy());
+        
+        let entries = fs::read_dir(log_dir)
+            .map_err(|e| format!("Failed to read log directory: {}", e))?;
+        
+        Ok(entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && *path != log_path)
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&rotated_prefix))
+            })
+            .collect())
+    }
+    
     pub fn log_to_file(app_handle: &AppHandle, level: &str, message: &str) {
         if let Ok(log_path) = Self::get_log_file_path(app_handle) {
             let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
@@ -103,7 +140,8 @@ impl FileManager {
             log::warn!("履歴アイテム数が制限を大幅に超過しています: {}", data.history.len());
         }
 
-        let json_content = serde_json::to_string(&data)
+        // 機密アイテムはディスクに書き出さない
+        let json_content = serde_json::to_string(&data.for_persistence())
             .map_err(|e| format!("Failed to serialize data: {}", e))?;
         
         // アトミックなファイル書き込み（一時ファイル経由）
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+
+// 最終利用からこの時間が経過するごとにスコアが半減する
+const HALF_LIFE_HOURS: f64 = 72.0;
+
+// 利用回数と最終利用時刻から頻度×新しさのスコアを算出
+pub fn score(access_count: u32, last_used: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let age_hours = (now - last_used).num_seconds().max(0) as f64 / 3600.0;
+    let decay = 0.5_f64.powf(age_hours / HALF_LIFE_HOURS);
+    (access_count as f64 + 1.0) * decay
+}
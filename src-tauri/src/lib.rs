@@ -3,15 +3,37 @@ mod file_manager;
 mod clipboard_monitor;
 mod window_manager;
 mod commands;
+mod sensitive_detector;
+mod tray_manager;
+mod capture_rules;
+mod content_classifier;
+mod eviction;
+mod frecency;
+mod dedup;
+mod session_recorder;
+mod retention;
+mod search;
+mod query;
+mod search_index;
+mod normalize;
+mod regex_search;
+mod sort;
+mod pagination;
+mod bookmark_folders;
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 use tauri::{AppHandle, State, Manager};
 use chrono::Utc;
 
-use models::{ClipboardItem, IpHistoryItem, AppData};
+use bookmark_folders::FolderTree;
+use dedup::Dedup;
+use models::{IpHistoryItem, AppData, CaptureState, MonitorStatus, RetentionPreview};
 use file_manager::FileManager;
 use clipboard_monitor::ClipboardMonitor;
+use search_index::SearchIndex;
+use session_recorder::SessionRecorder;
 use window_manager::WindowManager;
+use tray_manager::{TrayManager, TRAY_ID};
 use commands::*;
 
 
@@ -19,17 +41,20 @@ pub struct ClipboardManager {
     app_data: Arc<Mutex<AppData>>,
     monitor: ClipboardMonitor,
     hotkey_registered: Arc<Mutex<bool>>,
+    search_index: Arc<Mutex<SearchIndex>>,
 }
 
 impl ClipboardManager {
     pub fn new() -> Self {
         let app_data = Arc::new(Mutex::new(AppData::default()));
-        let monitor = ClipboardMonitor::new(Arc::clone(&app_data));
+        let search_index = Arc::new(Mutex::new(SearchIndex::default()));
+        let monitor = ClipboardMonitor::new(Arc::clone(&app_data), Arc::clone(&search_index));
         
         Self {
             app_data,
             monitor,
             hotkey_registered: Arc::new(Mutex::new(false)),
+            search_index,
         }
     }
 
@@ -38,31 +63,19 @@ impl ClipboardManager {
 
         match self.app_data.lock() {
             Ok(mut data) => {
+                // 読み込んだデータも次回の自動保存の対象にする（起動時の重複削除などを保存するため）
+                let revision = data.revision;
                 *data = loaded_data;
+                data.revision = revision;
+                data.touch();
                 
                 // 起動時の自動重複削除
                 let original_history_count = data.history.len();
                 let original_bookmarks_count = data.bookmarks.len();
                 
-                // クリップボード履歴の重複削除
-                use std::collections::HashMap;
-                let mut seen_content: HashMap<String, ClipboardItem> = HashMap::new();
-                
-                for item in data.history.iter() {
-                    let content_key = item.content.clone();
-                    
-                    if let Some(existing_item) = seen_content.get(&content_key) {
-                        if item.timestamp > existing_item.timestamp {
-                            seen_content.insert(content_key, item.clone());
-                        }
-                    } else {
-                        seen_content.insert(content_key, item.clone());
-                    }
-                }
-                
-                let mut unique_history: Vec<ClipboardItem> = seen_content.into_values().collect();
-                unique_history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-                data.history = unique_history;
+                // クリップボード履歴の重複削除（内容が完全に一致するものを最新のアイテムへ統合）
+                Dedup::dedup_history(&mut data.history);
+                data.history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
                 
                 // ブックマークの重複削除
                 let mut seen_bookmarks = std::collections::HashSet::new();
@@ -86,6 +99,17 @@ impl ClipboardManager {
                     log::info!("起動時自動重複削除: 履歴{}件、ブックマーク{}件を削除", history_removed, bookmarks_removed);
                 }
                 
+                // 存在しないフォルダへの参照などを修復
+                let folders_repaired = FolderTree::repair(&mut data);
+                if folders_repaired > 0 {
+                    log::warn!("ブックマークフォルダの参照を修復: {}件", folders_repaired);
+                }
+                
+                // 検索インデックスを読み込んだデータから構築
+                if let Ok(mut index) = self.search_index.lock() {
+                    index.rebuild(&data);
+                }
+                
                 log::info!("データファイルから読み込み完了");
                 Ok(())
             }
@@ -93,6 +117,14 @@ impl ClipboardManager {
         }
     }
 
+    // 検索に使うインデックスを返す（app_data のロックを保持した状態で呼ぶ）
+    // インデックスは各変更箇所で更新済み。ここでは正規化の設定変更と件数の食い違いだけを確認する
+    pub fn synced_search_index(&self, data: &AppData) -> Result<MutexGuard<'_, SearchIndex>, String> {
+        let mut index = self.search_index.lock().map_err(|_| "Failed to access search index")?;
+        index.refresh(data);
+        Ok(index)
+    }
+
     pub fn save_to_file(&self, app_handle: &AppHandle) -> Result<(), String> {
         let data_to_save = match self.app_data.lock() {
             Ok(data) => data.clone(),
@@ -133,6 +165,14 @@ impl ClipboardManager {
                 
                 // タイムスタンプでソート（新しい順）
                 data.recent_ips.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+                data.touch();
+
+                if let Ok(mut index) = self.search_index.lock() {
+                    if let Some(ip_item) = data.recent_ips.iter().find(|item| item.ip == ip) {
+                        index.upsert(ip_item);
+                    }
+                    index.prune(&data);
+                }
                 
                 Ok(())
             }
@@ -140,6 +180,34 @@ impl ClipboardManager {
         }
     }
 
+    // 貼り付けを利用履歴に記録（IDが無い場合は内容が一致するアイテムに記録）
+    pub fn record_paste(&self, content: &str, item_id: Option<&str>, item_type: Option<&str>, target_app: Option<String>) {
+        let Ok(mut data) = self.app_data.lock() else {
+            return;
+        };
+
+        if item_type != Some("clipboard") {
+            let bookmark = data.bookmarks.iter_mut().find(|bookmark| match item_id {
+                Some(id) => bookmark.id == id,
+                None => bookmark.content == content,
+            });
+            if let Some(bookmark) = bookmark {
+                bookmark.record_paste(target_app);
+                data.touch();
+                return;
+            }
+        }
+
+        let item = data.history.iter_mut().find(|item| match item_id {
+            Some(id) => item.id == id,
+            None => item.content == content,
+        });
+        if let Some(item) = item {
+            item.record_paste(target_app);
+            data.touch();
+        }
+    }
+
     pub fn add_item(&self, content: String, content_type: String) -> Result<(), String> {
         self.monitor.add_item(content, content_type)
     }
@@ -148,6 +216,23 @@ impl ClipboardManager {
         self.monitor.start_auto_save(app_handle);
     }
 
+    pub fn start_sensitive_expiry(&self) {
+        self.monitor.start_sensitive_expiry();
+    }
+
+    pub fn start_retention_scheduler(&self, app_handle: AppHandle) {
+        self.monitor.start_retention_scheduler(app_handle);
+    }
+
+    // 保持ポリシーを今すぐ適用
+    pub fn run_retention(&self, app_handle: &AppHandle) -> Result<RetentionPreview, String> {
+        ClipboardMonitor::run_retention(&self.app_data, &self.search_index, app_handle)
+    }
+
+    pub fn mark_self_write(&self, content: &str, item_id: Option<String>, item_type: Option<String>) {
+        self.monitor.mark_self_write(content, item_id, item_type);
+    }
+
     pub fn start_monitoring(&self, app_handle: AppHandle) -> Result<(), String> {
         self.monitor.start_monitoring(app_handle)
     }
@@ -155,6 +240,32 @@ impl ClipboardManager {
     pub fn stop_monitoring(&self) -> Result<(), String> {
         self.monitor.stop_monitoring()
     }
+
+    pub fn get_monitor_status(&self) -> Result<MonitorStatus, String> {
+        self.monitor.get_status()
+    }
+
+    pub fn session_recorder(&self) -> &SessionRecorder {
+        self.monitor.recorder()
+    }
+
+    // キャプチャ状態（一時停止・シークレットモード）を更新
+    pub fn update_capture_state<F>(&self, app_handle: &AppHandle, update: F) -> Result<CaptureState, String>
+    where
+        F: FnOnce(&mut CaptureState),
+    {
+        let state = self.monitor.update_capture_state(app_handle, update)?;
+
+        // 設定で有効な場合のみ再起動後も状態を保持
+        let persist = self.app_data.lock().map(|data| data.settings.persist_capture_state).unwrap_or(false);
+        if persist {
+            if let Err(e) = self.save_to_file(app_handle) {
+                log::warn!("自動保存エラー: {}", e);
+            }
+        }
+
+        Ok(state)
+    }
 }
 
 #[tauri::command]
@@ -169,9 +280,22 @@ async fn init_clipboard_manager(
         log::warn!("データファイル読み込みエラー: {}", e);
     }
     
+    // 保存されていたキャプチャ状態をトレイに反映
+    if let Err(e) = state.update_capture_state(&app_handle, |capture_state| {
+        capture_state.resume_if_expired();
+    }) {
+        log::warn!("キャプチャ状態の反映エラー: {}", e);
+    }
+    
     // 自動保存を開始
     state.start_auto_save(app_handle.clone());
     
+    // 機密アイテムの期限切れ削除を開始
+    state.start_sensitive_expiry();
+    
+    // 保持ポリシーの定期適用を開始
+    state.start_retention_scheduler(app_handle.clone());
+    
     // クリップボード監視を開始（エラーを無視）
     if let Err(e) = state.start_monitoring(app_handle.clone()) {
         log::warn!("クリップボード監視開始失敗: {}", e);
@@ -288,28 +412,43 @@ async fn get_permission_instructions() -> Result<serde_json::Value, String> {
 }
 
 // コンテンツ貼り付け機能
+// item_id / item_type ("clipboard" | "bookmark") を渡すと利用履歴に記録する
 #[tauri::command]
-async fn paste_content(content: String) -> Result<String, String> {
+async fn paste_content(
+    content: String,
+    item_id: Option<String>,
+    item_type: Option<String>,
+    state: State<'_, ClipboardManager>,
+) -> Result<String, String> {
     use std::process::Command;
     
+    // 監視側で新しいコピーとして取り込まれないよう書き込みを登録
+    state.mark_self_write(&content, item_id.clone(), item_type.clone());
+    
     // AppleScriptを使用してコンテンツをクリップボードに設定し、貼り付け
-    let script = format!(
-        r#"
-        set the clipboard to "{}"
-        tell application "System Events"
-            keystroke "v" using command down
-        end tell
-        "#,
-        content.replace("\"", "\\\"").replace("\n", "\\n")
-    );
+    // 内容はスクリプトに埋め込まず引数で渡す（エスケープによる書き換えを避け、登録したハッシュと一致させる）
+    let script = r#"
+        on run argv
+            set the clipboard to item 1 of argv
+            tell application "System Events"
+                keystroke "v" using command down
+            end tell
+        end run
+        "#;
     
     match Command::new("osascript")
         .arg("-e")
-        .arg(&script)
+        .arg(script)
+        .arg("--") // "-" で始まる内容をオプションとして解釈させない
+        .arg(&content)
         .output() {
         Ok(output) => {
             if output.status.success() {
                 log::info!("貼り付け成功: {} chars", content.len());
+                
+                // 貼り付け先（最前面のアプリ）を利用履歴に記録
+                let target_app = ClipboardMonitor::detect_source_app();
+                state.record_paste(&content, item_id.as_deref(), item_type.as_deref(), target_app);
                 Ok("Content pasted successfully".to_string())
             } else {
                 let error = String::from_utf8_lossy(&output.stderr);
@@ -374,20 +513,14 @@ pub fn run() {
       }
       
       // システムトレイメニューの設定
-      use tauri::{
-        menu::{Menu, MenuItem},
-        tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-      };
+      use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
       
-      let quit_item = MenuItem::with_id(app, "quit", "終了", true, None::<&str>).unwrap();
-      let show_item = MenuItem::with_id(app, "show", "ウィンドウを表示", true, None::<&str>).unwrap();
-      let hide_item = MenuItem::with_id(app, "hide", "ウィンドウを非表示", true, None::<&str>).unwrap();
-      let clear_item = MenuItem::with_id(app, "clear", "履歴をクリア", true, None::<&str>).unwrap();
+      let initial_state = CaptureState::default();
+      let menu = TrayManager::new(app.handle().clone()).build_menu(&initial_state).unwrap();
       
-      let menu = Menu::with_items(app, &[&show_item, &hide_item, &clear_item, &quit_item]).unwrap();
-      
-      let _tray = TrayIconBuilder::with_id("main-tray")
+      let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
+        .tooltip(TrayManager::tooltip(&initial_state))
         .on_menu_event(move |app, event| match event.id.as_ref() {
           "quit" => {
             log::info!("トレイメニュー: アプリケーション終了");
@@ -406,6 +539,35 @@ pub fn run() {
               let _ = window.hide();
             }
           }
+          "toggle_pause" => {
+            let manager = app.state::<ClipboardManager>();
+            match manager.update_capture_state(app, |capture_state| {
+              capture_state.paused = !capture_state.paused;
+              capture_state.paused_until = None;
+            }) {
+              Ok(capture_state) => log::info!("トレイメニュー: 監視{}", if capture_state.paused { "一時停止" } else { "再開" }),
+              Err(e) => log::warn!("トレイメニュー: 一時停止切替エラー: {}", e),
+            }
+          }
+          "pause_15" => {
+            log::info!("トレイメニュー: 15分間一時停止");
+            let manager = app.state::<ClipboardManager>();
+            if let Err(e) = manager.update_capture_state(app, |capture_state| {
+              capture_state.paused = true;
+              capture_state.paused_until = Some(Utc::now() + chrono::Duration::minutes(15));
+            }) {
+              log::warn!("トレイメニュー: 一時停止エラー: {}", e);
+            }
+          }
+          "toggle_incognito" => {
+            let manager = app.state::<ClipboardManager>();
+            match manager.update_capture_state(app, |capture_state| {
+              capture_state.incognito = !capture_state.incognito;
+            }) {
+              Ok(capture_state) => log::info!("トレイメニュー: シークレットモード {}", capture_state.incognito),
+              Err(e) => log::warn!("トレイメニュー: シークレットモード切替エラー: {}", e),
+            }
+          }
           "clear" => {
             log::info!("トレイメニュー: 履歴をクリア");
             // ここでクリップボード履歴をクリアする処理を追加
@@ -442,14 +604,34 @@ pub fn run() {
         paste_content,
         // commandsモジュールのコマンドを追加
         get_clipboard_history,
+        get_sorted_history,
+        get_clipboard_item_content,
         get_app_data,
+        get_item_content,
+        list_history,
+        list_bookmarks,
         get_bookmarks,
+        get_sorted_bookmarks,
+        get_bookmark_folders,
+        get_folder_contents,
+        create_bookmark_folder,
+        rename_bookmark_folder,
+        move_bookmark_folder,
+        delete_bookmark_folder,
+        move_bookmarks,
         add_bookmark,
         delete_bookmark,
         get_recent_ips,
         get_settings,
         update_settings,
+        test_capture_rules,
         stop_clipboard_monitoring,
+        start_clipboard_monitoring,
+        pause_clipboard_monitoring,
+        resume_clipboard_monitoring,
+        set_incognito_mode,
+        get_capture_state,
+        get_monitor_status,
         add_clipboard_item,
         save_data_to_file,
         load_data_from_file,
@@ -458,7 +640,10 @@ pub fn run() {
         detect_ips_in_text,
         delete_clipboard_item,
         clear_clipboard_history,
+        pin_clipboard_item,
+        unpin_clipboard_item,
         search_clipboard_history,
+        update_clipboard_item,
         get_clipboard_stats,
         update_bookmark,
         search_bookmarks,
@@ -466,14 +651,24 @@ pub fn run() {
         clear_all_bookmarks,
         clear_ip_history,
         search_ip_history,
+        search_all,
+        search_regex,
+        validate_regex_pattern,
+        get_saved_searches,
+        create_saved_search,
+        update_saved_search,
+        delete_saved_search,
+        run_saved_search,
+        get_saved_search_counts,
         reset_ip_count,
         find_duplicate_clipboard_items,
+        merge_clipboard_items,
         find_duplicate_bookmarks,
         get_app_logs,
         clear_app_logs,
         get_app_diagnostics,
-        cleanup_memory,
-        cleanup_old_items,
+        preview_retention,
+        apply_retention,
         register_global_hotkey,
         unregister_global_hotkey,
         show_main_window,
@@ -482,7 +677,12 @@ pub fn run() {
         hide_dock_icon,
         minimize_to_tray,
         restore_from_tray,
-        update_item_access
+        update_item_access,
+        get_item_timeline,
+        start_session_recording,
+        stop_session_recording,
+        get_session_recording_status,
+        replay_session_recording
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::sensitive_detector::SensitiveDetector;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardItem {
@@ -12,6 +15,133 @@ pub struct ClipboardItem {
     pub access_count: u32,
     #[serde(default)]
     pub last_accessed: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sensitive_kind: Option<String>,
+    #[serde(default)]
+    pub incognito: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub source_app: Option<String>,
+    #[serde(default)]
+    pub pinned: bool, // 件数制限・クリーンアップ・クリアの対象外
+    #[serde(default)]
+    pub first_seen: Option<DateTime<Utc>>, // 統合された重複を含む初回取得時刻
+    #[serde(default)]
+    pub copy_count: u32,
+    #[serde(default)]
+    pub last_copied: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub timeline: Vec<UsageEvent>,
+    #[serde(default = "default_selection")]
+    pub selection: String, // 取り込み元: "clipboard" | "primary"（X11 PRIMARY選択）
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+fn default_selection() -> String {
+    "clipboard".to_string()
+}
+
+impl ClipboardItem {
+    pub fn new(content: String, content_type: String) -> Self {
+        Self::captured_at(content, content_type, Utc::now())
+    }
+
+    // 取り込み時刻を指定して作成（セッション再生では記録時の時刻を使う）
+    pub fn captured_at(content: String, content_type: String, now: DateTime<Utc>) -> Self {
+        let size = content.len();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            content,
+            content_type,
+            timestamp: now,
+            size,
+            access_count: 0,
+            last_accessed: None,
+            sensitive_kind: None,
+            incognito: false,
+            tags: Vec::new(),
+            source_app: None,
+            pinned: false,
+            first_seen: Some(now),
+            copy_count: 1,
+            last_copied: Some(now),
+            timeline: vec![UsageEvent::at("copy", None, now)],
+            selection: default_selection(),
+            title: None,
+            note: None,
+        }
+    }
+
+    // 同じ内容が再度コピーされた: 最新扱いにしてコピー回数を加算
+    pub fn record_copy(&mut self, now: DateTime<Utc>) {
+        self.first_seen = Some(self.first_seen());
+        self.timestamp = now;
+        self.last_copied = Some(now);
+        self.copy_count = self.copy_count.max(1) + 1;
+        push_usage_event(&mut self.timeline, UsageEvent::at("copy", None, now));
+    }
+
+    pub fn record_paste(&mut self, target_app: Option<String>) {
+        push_usage_event(&mut self.timeline, UsageEvent::new("paste", target_app));
+    }
+
+    // 選択範囲の拡張など、同じコピー操作の続きとして内容を置き換える
+    pub fn replace_content(&mut self, content: String, content_type: String, now: DateTime<Utc>) {
+        self.size = content.len();
+        self.content = content;
+        self.content_type = content_type;
+        self.timestamp = now;
+        self.last_copied = Some(now);
+    }
+
+    pub fn first_seen(&self) -> DateTime<Utc> {
+        self.first_seen.unwrap_or(self.timestamp)
+    }
+
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive_kind.is_some()
+    }
+
+    // 機密アイテムの保持期限が切れたか（再コピーで延長されないよう初回取得時刻から数える）
+    pub fn is_sensitive_expired(&self, cutoff: DateTime<Utc>) -> bool {
+        self.is_sensitive() && self.first_seen() <= cutoff
+    }
+
+    // 一覧用ペイロード: 機密アイテムは内容をマスクする
+    pub fn masked(&self) -> Self {
+        let mut item = self.clone();
+        if let Some(kind) = &self.sensitive_kind {
+            item.content = SensitiveDetector::mask(kind);
+        }
+        item
+    }
+
+    // 軽量な一覧用ペイロード（機密アイテムのプレビューはマスクする）
+    pub fn summary(&self) -> ItemSummary {
+        let (preview, truncated) = match &self.sensitive_kind {
+            Some(kind) => (SensitiveDetector::mask(kind), false),
+            None => ItemSummary::preview(&self.content),
+        };
+        ItemSummary {
+            id: self.id.clone(),
+            kind: "history".to_string(),
+            title: self.title.clone(),
+            preview,
+            truncated,
+            content_type: self.content_type.clone(),
+            size: self.size,
+            line_count: self.content.lines().count(),
+            timestamp: self.timestamp,
+            access_count: self.access_count,
+            pinned: self.pinned,
+            sensitive: self.is_sensitive(),
+            tags: self.tags.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +156,163 @@ pub struct BookmarkItem {
     pub access_count: u32,
     #[serde(default)]
     pub last_accessed: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub timeline: Vec<UsageEvent>,
+    #[serde(default)]
+    pub folder_id: Option<String>, // None はルート
+}
+
+impl BookmarkItem {
+    pub fn new(name: String, content: String, content_type: String, tags: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            content,
+            content_type,
+            timestamp: Utc::now(),
+            tags,
+            access_count: 0,
+            last_accessed: None,
+            timeline: Vec::new(),
+            folder_id: None,
+        }
+    }
+
+    pub fn record_paste(&mut self, target_app: Option<String>) {
+        push_usage_event(&mut self.timeline, UsageEvent::new("paste", target_app));
+    }
+
+    pub fn summary(&self) -> ItemSummary {
+        let (preview, truncated) = ItemSummary::preview(&self.content);
+        ItemSummary {
+            id: self.id.clone(),
+            kind: "bookmark".to_string(),
+            title: Some(self.name.clone()),
+            preview,
+            truncated,
+            content_type: self.content_type.clone(),
+            size: self.content.len(),
+            line_count: self.content.lines().count(),
+            timestamp: self.timestamp,
+            access_count: self.access_count,
+            pinned: false,
+            sensitive: false,
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+// ブックマークのフォルダ（parent_id が None のものはルート直下）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkFolder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BookmarkFolder {
+    pub fn new(name: String, parent_id: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            parent_id,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// フォルダ内の一覧（folder が None の場合はルート）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderContents {
+    pub folder: Option<BookmarkFolder>,
+    pub path: Vec<BookmarkFolder>, // ルートからこのフォルダまで（パンくず表示用）
+    pub folders: Vec<BookmarkFolder>,
+    pub bookmarks: Vec<BookmarkItem>,
+}
+
+// プレビューに含める最大文字数
+const PREVIEW_CHARS: usize = 200;
+
+// 一覧表示用の要約（本文は get_item_content で取得する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSummary {
+    pub id: String,
+    pub kind: String,           // "history" | "bookmark"
+    pub title: Option<String>,  // 履歴のタイトル、ブックマークの名前
+    pub preview: String,        // 本文の先頭部分（機密アイテムはマスク済み）
+    pub truncated: bool,        // プレビューが本文の一部のみの場合true
+    pub content_type: String,
+    pub size: usize,            // 本文のバイト数
+    pub line_count: usize,
+    pub timestamp: DateTime<Utc>,
+    pub access_count: u32,
+    pub pinned: bool,
+    pub sensitive: bool,
+    pub tags: Vec<String>,
+}
+
+impl ItemSummary {
+    fn preview(content: &str) -> (String, bool) {
+        match content.char_indices().nth(PREVIEW_CHARS) {
+            Some((end, _)) => (content[..end].to_string(), true),
+            None => (content.to_string(), false),
+        }
+    }
+}
+
+// カーソル方式のページ（next_cursor を次の呼び出しに渡す、最後のページでは None）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: usize,
+}
+
+// 本文の一部（offset・length はバイト単位、UTF-8の文字境界に揃える）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentChunk {
+    pub id: String,
+    pub offset: usize,
+    pub length: usize,
+    pub total_size: usize,
+    pub data: String,
+    pub next_offset: Option<usize>, // 続きがある場合の次の offset
+}
+
+// アイテムごとに保持する利用イベントの上限
+const MAX_TIMELINE_EVENTS: usize = 100;
+
+// アイテムの利用履歴（コピー・貼り付け）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEvent {
+    pub kind: String, // "copy" | "paste"
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub target_app: Option<String>, // 貼り付け先アプリ（取得できた場合）
+}
+
+impl UsageEvent {
+    pub fn new(kind: &str, target_app: Option<String>) -> Self {
+        Self::at(kind, target_app, Utc::now())
+    }
+
+    pub fn at(kind: &str, target_app: Option<String>, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            timestamp,
+            target_app,
+        }
+    }
+}
+
+// 古いイベントから削除して上限件数を保つ
+pub fn push_usage_event(timeline: &mut Vec<UsageEvent>, event: UsageEvent) {
+    timeline.push(event);
+    if timeline.len() > MAX_TIMELINE_EVENTS {
+        let overflow = timeline.len() - MAX_TIMELINE_EVENTS;
+        timeline.drain(..overflow);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +329,273 @@ pub struct AppSettings {
     pub ip_limit: usize,
     pub auto_start: bool,
     pub show_notifications: bool,
+    #[serde(default)]
+    pub sensitive: SensitiveSettings,
+    #[serde(default)]
+    pub persist_capture_state: bool,
+    #[serde(default)]
+    pub capture_rules: Vec<CaptureRule>,
+    #[serde(default)]
+    pub eviction: EvictionSettings,
+    #[serde(default)]
+    pub dedup: DedupSettings,
+    #[serde(default)]
+    pub burst: BurstSettings,
+    #[serde(default)]
+    pub primary_selection: PrimarySelectionSettings,
+    #[serde(default)]
+    pub retention: RetentionSettings,
+    #[serde(default)]
+    pub search: SearchSettings,
+}
+
+// 検索時の文字の正規化（クエリと検索対象の両方に適用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+    pub normalize_width: bool, // NFKC互換の正規化（全角英数・半角カナ・濁点の合成など）
+    pub fold_kana: bool,       // カタカナとひらがなを区別しない
+    pub fold_case: bool,       // 大文字と小文字を区別しない
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            normalize_width: true,
+            fold_kana: true,
+            fold_case: true,
+        }
+    }
+}
+
+// 定期的に適用する保持ポリシー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    pub history: RetentionPolicy,    // ピン留めは対象外
+    pub recent_ips: RetentionPolicy,
+    pub logs: RetentionPolicy,       // アプリデータディレクトリ内のログファイル
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+            history: RetentionPolicy::default(),
+            recent_ips: RetentionPolicy::default(),
+            logs: RetentionPolicy {
+                max_age_days: Some(30),
+                max_count: Some(5),
+                max_size_bytes: Some(20 * 1024 * 1024),
+            },
+        }
+    }
+}
+
+// None の項目は制限しない（件数・サイズは新しいものから数えて超えた分を削除）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<i64>,
+    pub max_count: Option<usize>,
+    pub max_size_bytes: Option<u64>,
+}
+
+// 検索結果（アイテムのフィールドにスコアと一致範囲を加えて返す）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub score: f64,
+    pub matches: Vec<FieldMatch>,
+}
+
+// 横断検索の結果1件（kind: "history" | "bookmark" | "ip"）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SearchHit {
+    History(SearchResult<ClipboardItem>),
+    Bookmark(SearchResult<BookmarkItem>),
+    Ip(SearchResult<IpHistoryItem>),
+}
+
+impl SearchHit {
+    pub fn score(&self) -> f64 {
+        match self {
+            Self::History(result) => result.score,
+            Self::Bookmark(result) => result.score,
+            Self::Ip(result) => result.score,
+        }
+    }
+}
+
+// 横断検索で種類ごとに返す最大件数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchLimits {
+    pub history: usize,
+    pub bookmarks: usize,
+    pub ips: usize,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            history: 50,
+            bookmarks: 20,
+            ips: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchAllResponse {
+    pub results: Vec<SearchHit>,
+    // 件数上限を適用する前の一致件数
+    pub total_history: usize,
+    pub total_bookmarks: usize,
+    pub total_ips: usize,
+}
+
+// 正規表現検索の結果（score は一致範囲の数）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegexSearchResponse {
+    pub history: Vec<SearchResult<ClipboardItem>>,
+    pub bookmarks: Vec<SearchResult<BookmarkItem>>,
+    // 件数上限を適用する前の一致件数
+    pub total_history: usize,
+    pub total_bookmarks: usize,
+    pub timed_out: bool, // 検索時間の上限に達し、途中までの結果を返した場合true
+}
+
+// フィールド内の一致範囲（文字単位の [開始, 終了)）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMatch {
+    pub field: String,        // "title" | "name" | "tags" | "note" | "content" | "content_type"
+    pub index: Option<usize>, // tags の場合は何番目のタグか
+    pub ranges: Vec<[usize; 2]>,
+}
+
+// 保持ポリシーで削除される対象1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionCandidate {
+    pub id: String, // 履歴はアイテムID、IPはアドレス、ログはファイルパス
+    pub label: String,
+    pub size: u64,
+    pub timestamp: DateTime<Utc>,
+    pub reason: String, // "max_age" | "max_count" | "max_size"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPreview {
+    pub generated_at: DateTime<Utc>,
+    pub history: Vec<RetentionCandidate>,
+    pub recent_ips: Vec<RetentionCandidate>,
+    pub log_files: Vec<RetentionCandidate>,
+    pub total_bytes: u64,
+}
+
+// X11 PRIMARY選択の取り込み設定（Linux専用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrimarySelectionSettings {
+    pub enabled: bool,
+    pub min_length: usize,  // これより短い選択は取り込まない
+    pub debounce_ms: u64,   // 選択がこの時間変化しなくなってから取り込む
+    pub detect_ips: bool,
+}
+
+impl Default for PrimarySelectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_length: 3,
+            debounce_ms: 800,
+            detect_ips: false,
+        }
+    }
+}
+
+// 連続したクリップボード変更のまとめ方
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BurstSettings {
+    pub coalesce_window_ms: u64,    // この時間内の連続変更は最後の内容のみ取り込む（0 = 即時）
+    pub collapse_extensions: bool,  // 直前のアイテムを前後に拡張したコピーは置き換える
+    pub extension_window_secs: u64,
+}
+
+impl Default for BurstSettings {
+    fn default() -> Self {
+        Self {
+            coalesce_window_ms: 500,
+            collapse_extensions: true,
+            extension_window_secs: 5,
+        }
+    }
+}
+
+// 重複判定の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupSettings {
+    pub ignore_whitespace: bool,   // インデント・末尾空白・空行の違いを無視
+    pub ignore_case: bool,
+    pub similarity_threshold: f64, // 類似とみなすDice係数（1.0 = 正規化後の一致のみ）
+}
+
+impl Default for DedupSettings {
+    fn default() -> Self {
+        Self {
+            ignore_whitespace: true,
+            ignore_case: false,
+            similarity_threshold: 0.9,
+        }
+    }
+}
+
+// 履歴の退避ポリシー（history_limitの件数上限と組み合わせて適用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvictionSettings {
+    pub max_total_bytes: Option<usize>,
+    pub max_age_days: Option<i64>,
+    pub max_item_bytes: Option<usize>,          // これより大きいコピーは取り込まない
+    pub type_quotas: HashMap<String, usize>,     // content_type別の件数上限
+    pub strategy: String,                        // "oldest" | "lru" | "frecency"
+}
+
+impl Default for EvictionSettings {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: None,
+            max_age_days: None,
+            max_item_bytes: None,
+            type_quotas: HashMap::new(),
+            strategy: "oldest".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SensitiveSettings {
+    pub enabled: bool,
+    pub item_ttl_secs: u64,              // 0 = 自動削除しない
+    pub clear_clipboard_after_secs: u64, // 0 = システムクリップボードを消去しない
+}
+
+impl Default for SensitiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            item_ttl_secs: 300,
+            clear_clipboard_after_secs: 30,
+        }
+    }
 }
 
 impl Default for AppSettings {
@@ -52,6 +606,106 @@ impl Default for AppSettings {
             ip_limit: 10,
             auto_start: true,
             show_notifications: false,
+            sensitive: SensitiveSettings::default(),
+            persist_capture_state: false,
+            capture_rules: Vec::new(),
+            eviction: EvictionSettings::default(),
+            dedup: DedupSettings::default(),
+            burst: BurstSettings::default(),
+            primary_selection: PrimarySelectionSettings::default(),
+            retention: RetentionSettings::default(),
+            search: SearchSettings::default(),
+        }
+    }
+}
+
+// キャプチャ時に順番に評価されるユーザー定義ルール
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub conditions: RuleConditions,
+    #[serde(default)]
+    pub actions: Vec<RuleAction>,
+    #[serde(default)]
+    pub stop_processing: bool, // マッチしたら以降のルールを評価しない
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConditions {
+    pub pattern: Option<String>, // 正規表現
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub content_type: Option<String>,
+    pub source_app: Option<String>, // アプリ名の部分一致
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Ignore,
+    StripWhitespace,
+    Rewrite { pattern: String, replacement: String },
+    AddTags { tags: Vec<String> },
+    AutoBookmark { set: String },
+    SkipIpDetection,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// 監視の一時停止・シークレットモードの状態
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureState {
+    pub paused: bool,
+    pub paused_until: Option<DateTime<Utc>>, // 時間指定の一時停止
+    pub incognito: bool,                      // 履歴をメモリ上のみに保持
+}
+
+impl CaptureState {
+    // 時間指定の一時停止が期限切れなら再開する（状態が変わった場合true）
+    pub fn resume_if_expired(&mut self) -> bool {
+        match self.paused_until {
+            Some(until) if until <= Utc::now() => {
+                self.paused = false;
+                self.paused_until = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// クリップボード監視のヘルスステータス
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorStatus {
+    pub state: String, // "running" | "restarting" | "stopped"
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_capture_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub consecutive_errors: u32,
+    pub total_errors: u64,
+    pub restart_count: u32,
+}
+
+impl Default for MonitorStatus {
+    fn default() -> Self {
+        Self {
+            state: "stopped".to_string(),
+            started_at: None,
+            last_capture_at: None,
+            last_error: None,
+            last_error_at: None,
+            consecutive_errors: 0,
+            total_errors: 0,
+            restart_count: 0,
         }
     }
 }
@@ -63,6 +717,97 @@ pub struct AppData {
     pub bookmarks: Vec<BookmarkItem>,
     pub recent_ips: Vec<IpHistoryItem>,
     pub settings: AppSettings,
+    #[serde(default)]
+    pub capture_state: CaptureState,
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearch>,
+    #[serde(default)]
+    pub bookmark_folders: Vec<BookmarkFolder>,
+    #[serde(skip)]
+    pub revision: u64, // 変更のたびに増やす（自動保存の変更検出用、保存しない）
+}
+
+// 名前を付けて保存した検索クエリ（スマートコレクション、内容は評価時に決まる）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub query: String, // 検索クエリ構文（after:7d などの相対日付は評価時点から計算）
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedSearch {
+    pub fn new(name: String, query: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            query,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+// 保存した検索の一致件数（バッジ表示用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchCount {
+    pub id: String,
+    pub total: usize,
+    pub total_history: usize,
+    pub total_bookmarks: usize,
+    pub total_ips: usize,
+}
+
+// デバッグ用に記録したクリップボードセッション
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecording {
+    pub version: u32,
+    pub started_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+    pub include_content: bool,
+    pub settings: AppSettings, // 記録開始時の設定（再生時に同じ条件で処理するため）
+    pub changes: Vec<RecordedChange>,
+}
+
+// 監視が検出したクリップボード変更1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedChange {
+    pub offset_ms: u64, // 記録開始からの経過時間
+    pub timestamp: DateTime<Utc>,
+    pub selection: String, // "clipboard" | "primary"
+    pub hash: String,
+    pub length: usize,
+    #[serde(default)]
+    pub content: Option<String>, // include_content が有効な場合のみ
+    #[serde(default)]
+    pub source_app: Option<String>,
+    #[serde(default)]
+    pub concealed: bool,
+    #[serde(default)]
+    pub self_write: bool, // アプリ自身の書き込み（取り込み対象外）
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecordingStatus {
+    pub recording: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub include_content: bool,
+    pub change_count: usize,
+}
+
+// セッション再生の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub total_changes: usize,
+    pub replayed: usize,
+    pub captured: usize,
+    pub coalesced: usize,
+    pub skipped_self_writes: usize,
+    pub skipped_without_content: usize,
+    pub detected_ips: Vec<String>,
+    pub data: AppData, // 再生後のスクラッチデータ（機密アイテムはマスク済み）
 }
 
 impl Default for AppData {
@@ -73,6 +818,57 @@ impl Default for AppData {
             bookmarks: Vec::new(),
             recent_ips: Vec::new(),
             settings: AppSettings::default(),
+            capture_state: CaptureState::default(),
+            saved_searches: Vec::new(),
+            bookmark_folders: Vec::new(),
+            revision: 0,
         }
     }
-}
\ No newline at end of file
+}
+
+impl AppData {
+    // データを変更したことを記録する（自動保存の対象になる）
+    pub fn touch(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    // ファイル保存用のコピー: 機密アイテムとシークレットモード中のアイテムはディスクに書き出さない
+    pub fn for_persistence(&self) -> Self {
+        let mut data = self.clone();
+        data.history.retain(|item| !item.is_sensitive() && !item.incognito);
+        if !data.settings.persist_capture_state {
+            data.capture_state = CaptureState::default();
+        }
+        data
+    }
+
+    // フロントエンド送信用のコピー: 機密アイテムの内容をマスクする
+    pub fn masked(&self) -> Self {
+        let mut data = self.clone();
+        data.history = self.history.iter().map(|item| item.masked()).collect();
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn recopied_sensitive_item_still_expires_from_first_capture() {
+        let captured = Utc::now() - Duration::seconds(400);
+        let mut item = ClipboardItem::captured_at("ghp_secret".to_string(), "text".to_string(), captured);
+        item.sensitive_kind = Some("github_token".to_string());
+
+        item.record_copy(captured + Duration::seconds(200));
+        item.record_copy(captured + Duration::seconds(390));
+
+        let cutoff = Utc::now() - Duration::seconds(300);
+        assert!(item.timestamp > cutoff);
+        assert!(item.is_sensitive_expired(cutoff));
+
+        item.sensitive_kind = None;
+        assert!(!item.is_sensitive_expired(cutoff));
+    }
+}
@@ -0,0 +1,161 @@
+use unicode_normalization::char::{canonical_combining_class, decompose_compatible};
+use unicode_normalization::UnicodeNormalization;
+use crate::models::SearchSettings;
+
+// 正規化後の文字列と、各文字に対応する元のテキストの範囲（文字単位の [開始, 終了)）
+pub struct Normalized {
+    pub chars: Vec<char>,
+    spans: Vec<[usize; 2]>,
+}
+
+impl Normalized {
+    // 正規化後の範囲を元のテキストの範囲に戻す（一致範囲の表示用）
+    pub fn original_range(&self, range: [usize; 2]) -> [usize; 2] {
+        if range[0] >= range[1] || range[1] > self.spans.len() {
+            return range;
+        }
+        [self.spans[range[0]][0], self.spans[range[1] - 1][1]]
+    }
+}
+
+pub struct TextNormalizer;
+
+impl TextNormalizer {
+    // 検索用に正規化する: NFKC（normalize_width）、カタカナ→ひらがな（fold_kana）、小文字化（fold_case）
+    // NFKCは基底文字と後続の結合文字のまとまりごとに適用し、各文字に元のまとまりの範囲を対応させる
+    pub fn normalize(text: &str, settings: &SearchSettings) -> Normalized {
+        let source: Vec<char> = text.chars().collect();
+        let mut chars = Vec::with_capacity(source.len());
+        let mut spans = Vec::with_capacity(source.len());
+        let mut expanded: Vec<char> = Vec::new();
+        let mut pos = 0;
+
+        while pos < source.len() {
+            expanded.clear();
+            let consumed = if settings.normalize_width {
+                let end = Self::segment_end(&source, pos);
+                if end - pos == 1 && source[pos].is_ascii() {
+                    expanded.push(source[pos]); // ASCIIはNFKCで変化しない
+                } else {
+                    expanded.extend(source[pos..end].iter().copied().nfkc());
+                }
+                end - pos
+            } else {
+                expanded.push(source[pos]);
+                1
+            };
+
+            for &c in &expanded {
+                let c = if settings.fold_kana { Self::fold_kana(c) } else { c };
+                if settings.fold_case && c.is_uppercase() {
+                    for lower in c.to_lowercase() {
+                        chars.push(lower);
+                        spans.push([pos, pos + consumed]);
+                    }
+                } else {
+                    chars.push(c);
+                    spans.push([pos, pos + consumed]);
+                }
+            }
+            pos += consumed;
+        }
+
+        Normalized { chars, spans }
+    }
+
+    pub fn normalize_str(text: &str, settings: &SearchSettings) -> String {
+        Self::normalize(text, settings).chars.into_iter().collect()
+    }
+
+    // pos から始まるまとまり（基底文字と、互換分解すると結合文字になる後続の文字）の終わり
+    // 半角カナの濁点（ｶﾞ）や結合文字（e + ◌́）はNFKCで1文字に合成されるため同じまとまりにする
+    fn segment_end(source: &[char], pos: usize) -> usize {
+        let mut end = pos + 1;
+        while end < source.len() && Self::continues_segment(source[end]) {
+            end += 1;
+        }
+        end
+    }
+
+    fn continues_segment(c: char) -> bool {
+        if c.is_ascii() {
+            return false;
+        }
+        // ハングルの母音・終声字母は前の字母と合成される
+        if matches!(c as u32, 0x1161..=0x11C2) {
+            return true;
+        }
+        let mut first = None;
+        decompose_compatible(c, |decomposed| {
+            first.get_or_insert(decomposed);
+        });
+        first.is_some_and(|first| canonical_combining_class(first) != 0)
+    }
+
+    // カタカナをひらがなに寄せる（対応するひらがなが無い文字はそのまま）
+    fn fold_kana(c: char) -> char {
+        match c as u32 {
+            code @ (0x30A1..=0x30F6 | 0x30FD..=0x30FE) => char::from_u32(code - 0x60).unwrap_or(c),
+            _ => c,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> SearchSettings {
+        SearchSettings::default()
+    }
+
+    fn width_only() -> SearchSettings {
+        SearchSettings { normalize_width: true, fold_kana: false, fold_case: false }
+    }
+
+    #[test]
+    fn applies_nfkc() {
+        assert_eq!(TextNormalizer::normalize_str("cafe\u{301}", &width_only()), "café");
+        assert_eq!(TextNormalizer::normalize_str("10㎏", &width_only()), "10kg");
+        assert_eq!(TextNormalizer::normalize_str("ﬁle", &width_only()), "file");
+        assert_eq!(TextNormalizer::normalize_str("Ⅻ", &width_only()), "XII");
+        assert_eq!(TextNormalizer::normalize_str("Ⅻ", &all()), "xii");
+        assert_eq!(TextNormalizer::normalize_str("ＡＢＣ１２３", &all()), "abc123");
+        assert_eq!(TextNormalizer::normalize_str("ｶﾞｲﾄﾞ", &width_only()), "ガイド");
+        assert_eq!(TextNormalizer::normalize_str("ｶﾞｲﾄﾞ", &all()), "がいど");
+        assert_eq!(TextNormalizer::normalize_str("か\u{3099}", &width_only()), "が");
+        assert_eq!(TextNormalizer::normalize_str("①㈱\u{3000}", &width_only()), "1(株) ");
+        assert_eq!(TextNormalizer::normalize_str("\u{1100}\u{1161}", &width_only()), "가");
+    }
+
+    #[test]
+    fn keeps_text_when_disabled() {
+        let none = SearchSettings { normalize_width: false, fold_kana: false, fold_case: false };
+        assert_eq!(TextNormalizer::normalize_str("ＡＢＣ ｶﾞ ㎏", &none), "ＡＢＣ ｶﾞ ㎏");
+    }
+
+    #[test]
+    fn maps_ranges_back_to_the_original_text() {
+        // 合成: e + ◌́ の2文字が é の1文字になる
+        let normalized = TextNormalizer::normalize("cafe\u{301} au lait", &all());
+        assert_eq!(normalized.chars.iter().collect::<String>(), "café au lait");
+        assert_eq!(normalized.original_range([3, 4]), [3, 5]);
+        assert_eq!(normalized.original_range([5, 7]), [6, 8]);
+
+        // 展開: ㎏ の1文字が kg の2文字になる
+        let normalized = TextNormalizer::normalize("10㎏です", &all());
+        assert_eq!(normalized.chars.iter().collect::<String>(), "10kgです");
+        assert_eq!(normalized.original_range([2, 3]), [2, 3]);
+        assert_eq!(normalized.original_range([2, 4]), [2, 3]);
+        assert_eq!(normalized.original_range([4, 6]), [3, 5]);
+
+        // 半角カナの濁点: ｶﾞ の2文字が が の1文字になる
+        let normalized = TextNormalizer::normalize("ｶﾞｲﾄﾞ", &all());
+        assert_eq!(normalized.original_range([0, 1]), [0, 2]);
+        assert_eq!(normalized.original_range([1, 3]), [2, 5]);
+
+        // 範囲外・空の範囲はそのまま返す
+        assert_eq!(normalized.original_range([2, 2]), [2, 2]);
+        assert_eq!(normalized.original_range([0, 10]), [0, 10]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::models::{ContentChunk, Page};
+
+// 1ページの件数（省略時）と上限
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+// 本文を分割して取得する際の1回あたりのバイト数（省略時）と上限
+const DEFAULT_CHUNK_BYTES: usize = 256 * 1024;
+const MAX_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+
+pub struct Paginator;
+
+impl Paginator {
+    // 並べ替え済みのアイテムから cursor の続きを1ページ分返す
+    // カーソルは「位置:最後に返したアイテムのID」で、前回の呼び出し以降に追加・削除があっても
+    // 最後のアイテムの直後から続ける（そのアイテムが削除されていれば位置から続ける）
+    pub fn page<T, S, I, F>(items: &[&T], cursor: Option<&str>, limit: Option<usize>, id_of: I, summarize: F) -> Result<Page<S>, String>
+    where
+        I: Fn(&T) -> &str,
+        F: Fn(&T) -> S,
+    {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let start = match cursor {
+            Some(cursor) => Self::resolve_cursor(items, cursor, &id_of)?,
+            None => 0,
+        };
+        let end = (start + limit).min(items.len());
+
+        let next_cursor = (end < items.len()).then(|| format!("{}:{}", end, id_of(items[end - 1])));
+        Ok(Page {
+            items: items[start..end].iter().map(|item| summarize(item)).collect(),
+            next_cursor,
+            total: items.len(),
+        })
+    }
+
+    fn resolve_cursor<T, I>(items: &[&T], cursor: &str, id_of: &I) -> Result<usize, String>
+    where
+        I: Fn(&T) -> &str,
+    {
+        let (position, id) = cursor
+            .split_once(':')
+            .and_then(|(position, id)| Some((position.parse::<usize>().ok()?, id)))
+            .ok_or_else(|| format!("Invalid cursor: {}", cursor))?;
+
+        Ok(items
+            .iter()
+            .position(|item| id_of(item) == id)
+            .map_or(position.min(items.len()), |found| found + 1))
+    }
+
+    // 本文の offset バイト目から最大 length バイトを返す
+    // 範囲は文字の途中で切れないよう前方の文字境界に揃える（1文字が length を超える場合はその1文字を返す）
+    pub fn chunk(id: &str, content: &str, offset: Option<usize>, length: Option<usize>) -> Result<ContentChunk, String> {
+        let total_size = content.len();
+        let offset = offset.unwrap_or(0);
+        if offset > total_size {
+            return Err(format!("Offset {} is beyond the content size {}", offset, total_size));
+        }
+        let length = length.unwrap_or(DEFAULT_CHUNK_BYTES).clamp(1, MAX_CHUNK_BYTES);
+
+        let start = Self::floor_char_boundary(content, offset);
+        let mut end = Self::floor_char_boundary(content, (start + length).min(total_size));
+        if end == start && start < total_size {
+            end = content[start..].char_indices().nth(1).map_or(total_size, |(next, _)| start + next);
+        }
+
+        Ok(ContentChunk {
+            id: id.to_string(),
+            offset: start,
+            length: end - start,
+            total_size,
+            data: content[start..end].to_string(),
+            next_offset: (end < total_size).then_some(end),
+        })
+    }
+
+    fn floor_char_boundary(content: &str, index: usize) -> usize {
+        (0..=index).rev().find(|&i| content.is_char_boundary(i)).unwrap_or(0)
+    }
+}
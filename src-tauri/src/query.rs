@@ -0,0 +1,388 @@
+use std::fmt;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+
+// 検索クエリの構文:
+//   語             あいまい一致（-語 は部分一致しないもの）
+//   "フレーズ"     大文字小文字を区別しない部分一致
+//   type:url  tag:prod  app:Terminal  name:xxx  note:xxx  is:pinned  kind:bookmark
+//   after:2026-09-01  before:2026-09-30  on:2026-09-15（after はその日を含み、before は含まない）
+//   after:7d  after:2w  on:today  on:yesterday（評価時点からの相対日付）
+//   日付の区切りはローカルタイムゾーンの0時
+//   size>2k  size<=1m  size=100
+//   先頭の - で否定、条件はすべてAND
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Term(String),
+    Phrase(String),
+    Type(String),
+    Tag(String),
+    App(String),
+    Name(String),
+    Note(String),
+    Is(String),
+    Kind(String), // "history" | "bookmark" | "ip"
+    After(DateTime<Utc>),
+    Before(DateTime<Utc>),
+    On(NaiveDate),
+    Size(Comparison, u64),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    pub fn matches(self, actual: u64, expected: u64) -> bool {
+        match self {
+            Self::Greater => actual > expected,
+            Self::GreaterOrEqual => actual >= expected,
+            Self::Less => actual < expected,
+            Self::LessOrEqual => actual <= expected,
+            Self::Equal => actual == expected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub filters: Vec<Filter>, // すべて満たすものが一致
+}
+
+impl Query {
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+// 構文エラー（position はクエリ先頭からの文字位置）
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid query at position {}: {}", self.position, self.message)
+    }
+}
+
+const KEYS: &[&str] = &["type", "tag", "app", "name", "title", "note", "is", "kind", "after", "before", "on", "size"];
+
+pub struct QueryParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl QueryParser {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut parser = Self { chars: input.chars().collect(), pos: 0 };
+        let mut filters = Vec::new();
+
+        loop {
+            parser.skip_whitespace();
+            if parser.pos >= parser.chars.len() {
+                break;
+            }
+            filters.push(parser.parse_filter()?);
+        }
+
+        Ok(Query { filters })
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter, QueryError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            if self.peek().map_or(true, char::is_whitespace) {
+                return Err(self.error(start, "'-' must be followed by a term or filter"));
+            }
+            let filter = self.parse_positive()?;
+            return Ok(Filter::Not(Box::new(filter)));
+        }
+        self.parse_positive()
+    }
+
+    fn parse_positive(&mut self) -> Result<Filter, QueryError> {
+        if self.peek() == Some('"') {
+            let phrase_start = self.pos;
+            let phrase = self.parse_quoted()?;
+            if phrase.trim().is_empty() {
+                return Err(self.error(phrase_start, "empty phrase"));
+            }
+            return Ok(Filter::Phrase(phrase));
+        }
+
+        // 既知のキーに区切り文字が続く場合のみ条件として扱う（URLなどは通常の語）
+        let start = self.pos;
+        let key: String = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_lowercase();
+        let after_key = start + key.chars().count();
+        let separator = self.chars.get(after_key).copied();
+
+        if KEYS.contains(&key.as_str()) && matches!(separator, Some(':' | '>' | '<' | '=')) {
+            self.pos = after_key;
+            return self.parse_condition(&key, start);
+        }
+
+        Ok(Filter::Term(self.read_word()))
+    }
+
+    fn parse_condition(&mut self, key: &str, start: usize) -> Result<Filter, QueryError> {
+        let operator_start = self.pos;
+        let comparison = self.parse_operator();
+        if key != "size" && comparison.is_some() {
+            return Err(self.error(operator_start, &format!("'{}' only supports ':'", key)));
+        }
+
+        let value_start = self.pos;
+        let value = if self.peek() == Some('"') { self.parse_quoted()? } else { self.read_word() };
+        if value.is_empty() {
+            return Err(self.error(value_start, &format!("missing value for '{}'", key)));
+        }
+
+        let filter = match key {
+            "type" => Filter::Type(value.to_lowercase()),
+            "tag" => Filter::Tag(value.to_lowercase()),
+            "app" => Filter::App(value.to_lowercase()),
+            "name" | "title" => Filter::Name(value.to_lowercase()),
+            "note" => Filter::Note(value.to_lowercase()),
+            "is" => match value.to_lowercase().as_str() {
+                flag @ ("pinned" | "sensitive") => Filter::Is(flag.to_string()),
+                _ => return Err(self.error(value_start, "expected 'pinned' or 'sensitive'")),
+            },
+            "kind" => match value.to_lowercase().as_str() {
+                "history" => Filter::Kind("history".to_string()),
+                "bookmark" | "bookmarks" => Filter::Kind("bookmark".to_string()),
+                "ip" | "ips" => Filter::Kind("ip".to_string()),
+                _ => return Err(self.error(value_start, "expected 'history', 'bookmark' or 'ip'")),
+            },
+            "after" | "before" | "on" => {
+                let date = Self::parse_date(&value)
+                    .ok_or_else(|| self.error(value_start, "expected a date like 2026-09-01, today or 7d"))?;
+                match key {
+                    "after" => Filter::After(Self::start_of_day(date)),
+                    "before" => Filter::Before(Self::start_of_day(date)),
+                    _ => Filter::On(date),
+                }
+            }
+            "size" => {
+                let bytes = Self::parse_size(&value)
+                    .ok_or_else(|| self.error(value_start, "expected a size like 512, 2k or 1m"))?;
+                Filter::Size(comparison.unwrap_or(Comparison::Equal), bytes)
+            }
+            _ => return Err(self.error(start, &format!("unknown filter '{}'", key))),
+        };
+
+        Ok(filter)
+    }
+
+    // ':' は None（size の場合は等号扱い）
+    fn parse_operator(&mut self) -> Option<Comparison> {
+        let (comparison, length) = match (self.peek(), self.chars.get(self.pos + 1).copied()) {
+            (Some('>'), Some('=')) => (Some(Comparison::GreaterOrEqual), 2),
+            (Some('<'), Some('=')) => (Some(Comparison::LessOrEqual), 2),
+            (Some('>'), _) => (Some(Comparison::Greater), 1),
+            (Some('<'), _) => (Some(Comparison::Less), 1),
+            (Some('='), _) => (Some(Comparison::Equal), 1),
+            _ => (None, 1), // ':'
+        };
+        self.pos += length;
+        comparison
+    }
+
+    // "..." を読み取る（\" と \\ はエスケープ）
+    fn parse_quoted(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut value = String::new();
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' if matches!(self.peek(), Some('"' | '\\')) => {
+                    value.push(self.chars[self.pos]);
+                    self.pos += 1;
+                }
+                _ => value.push(c),
+            }
+        }
+
+        Err(self.error(start, "unterminated quote"))
+    }
+
+    fn read_word(&mut self) -> String {
+        let word: String = self.chars[self.pos..].iter().take_while(|c| !c.is_whitespace()).collect();
+        self.pos += word.chars().count();
+        word
+    }
+
+    // 2026-09-01 のほか today / yesterday / 7d（7日前）/ 2w（2週間前）を受け付ける
+    fn parse_date(value: &str) -> Option<NaiveDate> {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(date);
+        }
+
+        let today = Local::now().date_naive();
+        let lower = value.to_lowercase();
+        let days_ago = match lower.as_str() {
+            "today" => 0,
+            "yesterday" => 1,
+            _ => {
+                let (number, multiplier) = match (lower.strip_suffix('d'), lower.strip_suffix('w')) {
+                    (Some(number), _) => (number, 1),
+                    (_, Some(number)) => (number, 7),
+                    _ => return None,
+                };
+                let number: i64 = number.parse().ok().filter(|number| *number >= 0)?;
+                number.checked_mul(multiplier)?
+            }
+        };
+        today.checked_sub_signed(chrono::Duration::try_days(days_ago)?)
+    }
+
+    // 512, 2k, 1.5m, 1g（k/m/g は1024倍単位、末尾の b は省略可）
+    fn parse_size(value: &str) -> Option<u64> {
+        let lower = value.to_lowercase();
+        let lower = lower.strip_suffix('b').unwrap_or(&lower);
+        let (number, multiplier) = match lower.chars().last()? {
+            'k' => (&lower[..lower.len() - 1], 1024.0),
+            'm' => (&lower[..lower.len() - 1], 1024.0 * 1024.0),
+            'g' => (&lower[..lower.len() - 1], 1024.0 * 1024.0 * 1024.0),
+            _ => (lower, 1.0),
+        };
+        let number: f64 = number.parse().ok()?;
+        let bytes = number * multiplier;
+        // inf・NaN や u64 に収まらない値は u64::MAX に丸めず拒否する
+        (bytes.is_finite() && bytes >= 0.0 && bytes < u64::MAX as f64).then_some(bytes as u64)
+    }
+
+    // ローカルタイムゾーンでのその日の0時（夏時間で0時が存在しない日はUTCの0時）
+    fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, position: usize, message: &str) -> QueryError {
+        QueryError {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<Filter> {
+        QueryParser::parse(input).unwrap().filters
+    }
+
+    fn error_at(input: &str) -> usize {
+        QueryParser::parse(input).unwrap_err().position
+    }
+
+    #[test]
+    fn parses_terms_phrases_and_negation() {
+        assert_eq!(
+            parse(r#"deploy "exit code" -draft -"rm -rf""#),
+            vec![
+                Filter::Term("deploy".to_string()),
+                Filter::Phrase("exit code".to_string()),
+                Filter::Not(Box::new(Filter::Term("draft".to_string()))),
+                Filter::Not(Box::new(Filter::Phrase("rm -rf".to_string()))),
+            ]
+        );
+        assert_eq!(parse(r#""say \"hi\"""#), vec![Filter::Phrase(r#"say "hi""#.to_string())]);
+        assert!(parse("   ").is_empty());
+    }
+
+    #[test]
+    fn parses_keyed_filters() {
+        assert_eq!(
+            parse("type:URL tag:Prod app:\"Google Chrome\" title:notes is:pinned kind:bookmarks"),
+            vec![
+                Filter::Type("url".to_string()),
+                Filter::Tag("prod".to_string()),
+                Filter::App("google chrome".to_string()),
+                Filter::Name("notes".to_string()),
+                Filter::Is("pinned".to_string()),
+                Filter::Kind("bookmark".to_string()),
+            ]
+        );
+        // 既知のキーでなければ通常の語
+        assert_eq!(parse("https://example.com"), vec![Filter::Term("https://example.com".to_string())]);
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse("size>2k"), vec![Filter::Size(Comparison::Greater, 2048)]);
+        assert_eq!(parse("size<=1.5m"), vec![Filter::Size(Comparison::LessOrEqual, 1_572_864)]);
+        assert_eq!(parse("size:100b"), vec![Filter::Size(Comparison::Equal, 100)]);
+        assert!(QueryParser::parse("size>1e400").is_err());
+        assert!(QueryParser::parse("size>inf").is_err());
+        assert!(QueryParser::parse("size>nan").is_err());
+        assert!(QueryParser::parse("size>-1").is_err());
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        assert_eq!(error_at("foo -"), 4);
+        assert_eq!(error_at("foo \"bar"), 4);
+        assert_eq!(error_at("a \"  \""), 2);
+        assert_eq!(error_at("tag:"), 4);
+        assert_eq!(error_at("tag>x"), 3);
+        assert_eq!(error_at("is:maybe"), 3);
+        assert_eq!(error_at("kind:file"), 5);
+        assert_eq!(error_at("after:someday"), 6);
+        assert_eq!(error_at("x size>big"), 7);
+    }
+
+    #[test]
+    fn after_includes_the_given_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        assert_eq!(parse("after:2026-09-01"), vec![Filter::After(QueryParser::start_of_day(date))]);
+        assert_eq!(parse("before:2026-09-01"), vec![Filter::Before(QueryParser::start_of_day(date))]);
+        assert_eq!(parse("on:2026-09-01"), vec![Filter::On(date)]);
+
+        let midnight = Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).earliest().unwrap();
+        assert_eq!(QueryParser::start_of_day(date), midnight.with_timezone(&Utc));
+    }
+
+    #[test]
+    fn resolves_relative_dates_in_local_time() {
+        let today = Local::now().date_naive();
+        let days_ago = |days: i64| today - chrono::Duration::days(days);
+
+        assert_eq!(parse("on:today"), vec![Filter::On(today)]);
+        assert_eq!(parse("on:Yesterday"), vec![Filter::On(days_ago(1))]);
+        assert_eq!(parse("after:7d"), vec![Filter::After(QueryParser::start_of_day(days_ago(7)))]);
+        assert_eq!(parse("after:2w"), vec![Filter::After(QueryParser::start_of_day(days_ago(14)))]);
+        assert_eq!(parse("after:0d"), vec![Filter::After(QueryParser::start_of_day(today))]);
+    }
+}
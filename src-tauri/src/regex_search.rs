@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+use regex::{Regex, RegexBuilder};
+use crate::models::FieldMatch;
+use crate::search::Searchable;
+
+// パターンの最大文字数
+const MAX_PATTERN_CHARS: usize = 1000;
+// コンパイル後の正規表現・DFAキャッシュのサイズ上限（バイト）
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+const REGEX_DFA_SIZE_LIMIT: usize = 2 * 1024 * 1024;
+// 1回の検索にかける時間の上限（超えた場合はそこまでの結果を返す）
+const SEARCH_TIME_LIMIT: Duration = Duration::from_millis(500);
+// 1フィールドあたりに返す一致範囲の上限
+const MAX_SPANS_PER_FIELD: usize = 100;
+
+// 正規表現検索（regexクレートは一致判定が入力長に対して線形時間のため、
+// 病的なパターンでも時間はサイズ上限と検索時間の上限で抑えられる）
+pub struct RegexSearch {
+    regex: Regex,
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+impl RegexSearch {
+    // パターンを検証してコンパイルする（エラーはフロントエンドにそのまま表示できる文字列）
+    pub fn compile(pattern: &str, case_insensitive: bool) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Pattern cannot be empty".to_string());
+        }
+        if pattern.chars().count() > MAX_PATTERN_CHARS {
+            return Err(format!("Pattern is too long (max {} characters)", MAX_PATTERN_CHARS));
+        }
+
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .multi_line(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .dfa_size_limit(REGEX_DFA_SIZE_LIMIT)
+            .build()
+            .map_err(|e| match e {
+                regex::Error::CompiledTooBig(_) => "Pattern is too complex".to_string(),
+                e => format!("Invalid pattern: {}", e),
+            })?;
+
+        Ok(Self {
+            regex,
+            deadline: None,
+            timed_out: false,
+        })
+    }
+
+    // 検索時間の計測を開始する
+    pub fn start(&mut self) {
+        self.deadline = Some(Instant::now() + SEARCH_TIME_LIMIT);
+        self.timed_out = false;
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    // 一致したアイテムと各フィールドの一致範囲を返す（時間切れの場合はそこまでの結果）
+    pub fn find_all<'a, T: Searchable>(&mut self, items: &'a [T]) -> Vec<(&'a T, Vec<FieldMatch>)> {
+        let mut results = Vec::new();
+
+        for item in items {
+            if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.timed_out = true;
+                break;
+            }
+
+            let matches: Vec<FieldMatch> = item
+                .search_fields()
+                .iter()
+                .filter_map(|field| {
+                    let ranges = self.find_ranges(field.text);
+                    (!ranges.is_empty()).then(|| FieldMatch {
+                        field: field.name.to_string(),
+                        index: field.index,
+                        ranges,
+                    })
+                })
+                .collect();
+
+            if !matches.is_empty() {
+                results.push((item, matches));
+            }
+        }
+
+        results
+    }
+
+    // 一致範囲をバイト位置から文字位置（[開始, 終了)）に変換して返す
+    fn find_ranges(&self, text: &str) -> Vec<[usize; 2]> {
+        let mut ranges = Vec::new();
+        let mut byte_pos = 0;
+        let mut char_pos = 0;
+
+        for found in self.regex.find_iter(text).filter(|found| !found.is_empty()).take(MAX_SPANS_PER_FIELD) {
+            char_pos += text[byte_pos..found.start()].chars().count();
+            let start = char_pos;
+            char_pos += found.as_str().chars().count();
+            byte_pos = found.end();
+            ranges.push([start, char_pos]);
+        }
+
+        ranges
+    }
+}
@@ -0,0 +1,145 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use crate::models::{AppData, RetentionCandidate, RetentionPolicy, RetentionPreview, RetentionSettings};
+
+// 保持ポリシーの判定対象（新しい順に並べて渡す）
+struct Entry {
+    id: String,
+    label: String,
+    size: u64,
+    timestamp: DateTime<Utc>,
+}
+
+pub struct Retention;
+
+impl Retention {
+    // 削除対象を算出する（データ・ファイルは変更しない）
+    pub fn plan(data: &AppData, log_paths: &[PathBuf], settings: &RetentionSettings, now: DateTime<Utc>) -> RetentionPreview {
+        let mut history: Vec<Entry> = data
+            .history
+            .iter()
+            .filter(|item| !item.pinned)
+            .map(|item| Entry {
+                id: item.id.clone(),
+                label: Self::label(&item.masked().content),
+                size: item.size as u64,
+                timestamp: item.timestamp,
+            })
+            .collect();
+        history.sort_by_key(|entry| Reverse(entry.timestamp));
+
+        let mut recent_ips: Vec<Entry> = data
+            .recent_ips
+            .iter()
+            .map(|ip_item| Entry {
+                id: ip_item.ip.clone(),
+                label: ip_item.ip.clone(),
+                size: ip_item.ip.len() as u64,
+                timestamp: ip_item.timestamp,
+            })
+            .collect();
+        recent_ips.sort_by_key(|entry| Reverse(entry.timestamp));
+
+        let mut log_files: Vec<Entry> = log_paths
+            .iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(path).ok()?;
+                Some(Entry {
+                    id: path.to_string_lossy().to_string(),
+                    label: path.file_name()?.to_string_lossy().to_string(),
+                    size: metadata.len(),
+                    timestamp: metadata.modified().ok()?.into(),
+                })
+            })
+            .collect();
+        log_files.sort_by_key(|entry| Reverse(entry.timestamp));
+
+        let history = Self::select(history, &settings.history, now);
+        let recent_ips = Self::select(recent_ips, &settings.recent_ips, now);
+        let log_files = Self::select(log_files, &settings.logs, now);
+        let total_bytes = history
+            .iter()
+            .chain(&recent_ips)
+            .chain(&log_files)
+            .map(|candidate| candidate.size)
+            .sum();
+
+        RetentionPreview {
+            generated_at: now,
+            history,
+            recent_ips,
+            log_files,
+            total_bytes,
+        }
+    }
+
+    // ポリシーを超えたエントリを選ぶ（最初に該当したルールを理由とする）
+    fn select(entries: Vec<Entry>, policy: &RetentionPolicy, now: DateTime<Utc>) -> Vec<RetentionCandidate> {
+        let cutoff = policy.max_age_days.map(|days| now - chrono::Duration::days(days));
+        let mut kept_count = 0;
+        let mut kept_bytes: u64 = 0;
+        let mut candidates = Vec::new();
+
+        for entry in entries {
+            let reason = if cutoff.is_some_and(|cutoff| entry.timestamp < cutoff) {
+                Some("max_age")
+            } else if policy.max_count.is_some_and(|max_count| kept_count >= max_count) {
+                Some("max_count")
+            } else if policy.max_size_bytes.is_some_and(|max_size| kept_bytes + entry.size > max_size) {
+                Some("max_size")
+            } else {
+                None
+            };
+
+            match reason {
+                Some(reason) => candidates.push(RetentionCandidate {
+                    id: entry.id,
+                    label: entry.label,
+                    size: entry.size,
+                    timestamp: entry.timestamp,
+                    reason: reason.to_string(),
+                }),
+                None => {
+                    kept_count += 1;
+                    kept_bytes += entry.size;
+                }
+            }
+        }
+
+        candidates
+    }
+
+    fn label(content: &str) -> String {
+        let first_line = content.lines().next().unwrap_or_default();
+        let mut label: String = first_line.chars().take(50).collect();
+        if first_line.chars().count() > 50 || content.lines().nth(1).is_some() {
+            label.push('…');
+        }
+        label
+    }
+
+    // 算出済みの削除対象を履歴・IP履歴から削除し、削除件数を返す
+    pub fn apply_to_data(data: &mut AppData, preview: &RetentionPreview) -> usize {
+        let original_count = data.history.len() + data.recent_ips.len();
+        data.history.retain(|item| !preview.history.iter().any(|candidate| candidate.id == item.id));
+        data.recent_ips.retain(|ip_item| !preview.recent_ips.iter().any(|candidate| candidate.id == ip_item.ip));
+        original_count - (data.history.len() + data.recent_ips.len())
+    }
+
+    // 算出済みのログファイルを削除し、削除件数を返す
+    pub fn remove_log_files(preview: &RetentionPreview) -> usize {
+        preview
+            .log_files
+            .iter()
+            .filter(|candidate| match fs::remove_file(&candidate.id) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("ログファイル削除エラー: {} ({})", candidate.label, e);
+                    false
+                }
+            })
+            .count()
+    }
+}
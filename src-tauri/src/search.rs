@@ -0,0 +1,469 @@
+use std::collections::HashSet;
+use chrono::{DateTime, Local, Utc};
+use crate::frecency;
+use crate::models::{BookmarkItem, ClipboardItem, FieldMatch, IpHistoryItem, SearchSettings};
+use crate::normalize::{Normalized, TextNormalizer};
+use crate::query::{Filter, Query};
+
+// 部分列一致を判定するのはテキストの先頭からこの文字数まで（本文の奥の偶然の一致を拾わない）
+pub(crate) const MAX_FUZZY_PREFIX_CHARS: usize = 256;
+// 部分列一致で試す開始位置の上限
+const MAX_FUZZY_STARTS: usize = 64;
+// 部分列一致の範囲は検索語の文字数のこの倍数まで（それより疎らなものは一致とみなさない）
+const MAX_FUZZY_SPAN_RATIO: usize = 3;
+
+// フィールドごとの重み（名前・タグを本文より優先）
+const WEIGHT_NAME: f64 = 3.0;
+const WEIGHT_TAG: f64 = 2.5;
+const WEIGHT_NOTE: f64 = 1.5;
+const WEIGHT_CONTENT: f64 = 1.0;
+
+// 照合対象のフィールド
+pub struct Field<'a> {
+    pub name: &'static str,
+    pub index: Option<usize>, // タグの位置
+    pub text: &'a str,
+    pub weight: f64,
+}
+
+// 1フィールド内の一致結果（範囲は文字単位の [開始, 終了)）
+struct FuzzyMatch {
+    score: f64,
+    ranges: Vec<[usize; 2]>,
+}
+
+// 検索クエリで評価できるアイテム
+pub trait Searchable {
+    fn kind(&self) -> &'static str;
+    fn id(&self) -> &str;
+    fn search_fields(&self) -> Vec<Field<'_>>;
+    fn content_type(&self) -> &str;
+    fn timestamp(&self) -> DateTime<Utc>;
+    fn size(&self) -> usize;
+    fn usage(&self) -> (u32, DateTime<Utc>); // 利用回数と最終利用時刻（同点時の並び順に使う）
+    fn tags(&self) -> &[String] {
+        &[]
+    }
+    fn source_app(&self) -> Option<&str> {
+        None
+    }
+    fn name(&self) -> Option<&str> {
+        None
+    }
+    fn note(&self) -> Option<&str> {
+        None
+    }
+    fn has_flag(&self, _flag: &str) -> bool {
+        false
+    }
+}
+
+impl Searchable for ClipboardItem {
+    fn kind(&self) -> &'static str {
+        "history"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    // 機密アイテムの本文は対象外
+    fn search_fields(&self) -> Vec<Field<'_>> {
+        let mut fields = Vec::new();
+        if let Some(title) = &self.title {
+            fields.push(Field { name: "title", index: None, text: title, weight: WEIGHT_NAME });
+        }
+        for (index, tag) in self.tags.iter().enumerate() {
+            fields.push(Field { name: "tags", index: Some(index), text: tag, weight: WEIGHT_TAG });
+        }
+        if let Some(note) = &self.note {
+            fields.push(Field { name: "note", index: None, text: note, weight: WEIGHT_NOTE });
+        }
+        if !self.is_sensitive() {
+            fields.push(Field { name: "content", index: None, text: &self.content, weight: WEIGHT_CONTENT });
+        }
+        fields
+    }
+
+    fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn usage(&self) -> (u32, DateTime<Utc>) {
+        (self.access_count, self.last_accessed.unwrap_or(self.timestamp))
+    }
+
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    fn source_app(&self) -> Option<&str> {
+        self.source_app.as_deref()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        match flag {
+            "pinned" => self.pinned,
+            "sensitive" => self.is_sensitive(),
+            _ => false,
+        }
+    }
+}
+
+impl Searchable for BookmarkItem {
+    fn kind(&self) -> &'static str {
+        "bookmark"
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn search_fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![Field { name: "name", index: None, text: &self.name, weight: WEIGHT_NAME }];
+        for (index, tag) in self.tags.iter().enumerate() {
+            fields.push(Field { name: "tags", index: Some(index), text: tag, weight: WEIGHT_TAG });
+        }
+        fields.push(Field { name: "content", index: None, text: &self.content, weight: WEIGHT_CONTENT });
+        fields
+    }
+
+    fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    fn size(&self) -> usize {
+        self.content.len()
+    }
+
+    fn usage(&self) -> (u32, DateTime<Utc>) {
+        (self.access_count, self.last_accessed.unwrap_or(self.timestamp))
+    }
+
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+impl Searchable for IpHistoryItem {
+    fn kind(&self) -> &'static str {
+        "ip"
+    }
+
+    fn id(&self) -> &str {
+        &self.ip
+    }
+
+    fn search_fields(&self) -> Vec<Field<'_>> {
+        vec![Field { name: "ip", index: None, text: &self.ip, weight: WEIGHT_CONTENT }]
+    }
+
+    fn content_type(&self) -> &str {
+        "ip"
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    fn size(&self) -> usize {
+        self.ip.len()
+    }
+
+    fn usage(&self) -> (u32, DateTime<Utc>) {
+        (self.count, self.timestamp)
+    }
+}
+
+pub struct Search;
+
+impl Search {
+    // クエリに一致したアイテムを並べて返す
+    // スコア順、同点はピン留めを先頭に、利用頻度と新しさ（frecency）の高い順
+    // candidates を指定した場合はそのIDのアイテムだけを評価する（検索インデックスでの絞り込み）
+    pub fn rank<'a, T: Searchable>(
+        items: &'a [T],
+        query: &Query,
+        candidates: Option<&HashSet<String>>,
+        settings: &SearchSettings,
+    ) -> Vec<(&'a T, f64, Vec<FieldMatch>)> {
+        let now = Utc::now();
+        let query = &Self::normalize_query(query, settings);
+        let mut results: Vec<(&T, f64, Vec<FieldMatch>, f64)> = items
+            .iter()
+            .filter(|item| candidates.map_or(true, |ids| ids.contains(item.id())))
+            .filter_map(|item| {
+                let (score, matches) = Self::evaluate(item, query, settings)?;
+                let (count, last_used) = item.usage();
+                Some((item, score, matches, frecency::score(count, last_used, now)))
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then(b.0.has_flag("pinned").cmp(&a.0.has_flag("pinned")))
+                .then(b.3.total_cmp(&a.3))
+        });
+
+        results.into_iter().map(|(item, score, matches, _)| (item, score, matches)).collect()
+    }
+
+    // クエリに一致するアイテムの件数を返す（並べ替えや一致範囲の整理を行わない、件数表示用）
+    pub fn count<T: Searchable>(
+        items: &[T],
+        query: &Query,
+        candidates: Option<&HashSet<String>>,
+        settings: &SearchSettings,
+    ) -> usize {
+        let query = &Self::normalize_query(query, settings);
+        items
+            .iter()
+            .filter(|item| candidates.map_or(true, |ids| ids.contains(item.id())))
+            .filter(|item| Self::evaluate(*item, query, settings).is_some())
+            .count()
+    }
+
+    // クエリの語・値を検索設定に従って正規化する（検索対象のテキストにも同じ正規化を適用する）
+    fn normalize_query(query: &Query, settings: &SearchSettings) -> Query {
+        Query {
+            filters: query.filters.iter().map(|filter| Self::normalize_filter(filter, settings)).collect(),
+        }
+    }
+
+    fn normalize_filter(filter: &Filter, settings: &SearchSettings) -> Filter {
+        match filter {
+            Filter::Term(term) => Filter::Term(TextNormalizer::normalize_str(term, settings)),
+            Filter::Phrase(phrase) => Filter::Phrase(TextNormalizer::normalize_str(phrase, settings)),
+            Filter::Tag(value) => Filter::Tag(Self::normalize_label(value, settings)),
+            Filter::App(value) => Filter::App(Self::normalize_label(value, settings)),
+            Filter::Name(value) => Filter::Name(Self::normalize_label(value, settings)),
+            Filter::Note(value) => Filter::Note(Self::normalize_label(value, settings)),
+            Filter::Not(inner) => Filter::Not(Box::new(Self::normalize_filter(inner, settings))),
+            other => other.clone(),
+        }
+    }
+
+    // tag: app: name: note: の値は大文字小文字を常に区別しない
+    fn normalize_label(text: &str, settings: &SearchSettings) -> String {
+        TextNormalizer::normalize_str(text, settings).to_lowercase()
+    }
+
+    // 語・フレーズを含むか（含まない場合はフィールドの正規化を省略する）
+    fn has_text_filter(filters: &[Filter]) -> bool {
+        filters.iter().any(|filter| match filter {
+            Filter::Term(_) | Filter::Phrase(_) => true,
+            Filter::Not(inner) => Self::has_text_filter(std::slice::from_ref(inner.as_ref())),
+            _ => false,
+        })
+    }
+
+    // クエリ（正規化済み）のすべての条件を満たす場合にスコアと一致範囲を返す
+    // スコアは語・フレーズごとの最良フィールドスコア（重み付き）の合計
+    fn evaluate<T: Searchable>(item: &T, query: &Query, settings: &SearchSettings) -> Option<(f64, Vec<FieldMatch>)> {
+        let fields = item.search_fields();
+        let texts: Vec<Normalized> = if Self::has_text_filter(&query.filters) {
+            fields.iter().map(|field| TextNormalizer::normalize(field.text, settings)).collect()
+        } else {
+            Vec::new()
+        };
+        let mut matches: Vec<FieldMatch> = Vec::new();
+        let mut total_score = 0.0;
+
+        for filter in &query.filters {
+            total_score += Self::evaluate_filter(item, &fields, &texts, filter, settings, &mut matches)?;
+        }
+
+        for field_match in &mut matches {
+            field_match.ranges = Self::merge_ranges(std::mem::take(&mut field_match.ranges));
+        }
+        Some((total_score, matches))
+    }
+
+    fn evaluate_filter<T: Searchable>(
+        item: &T,
+        fields: &[Field],
+        texts: &[Normalized],
+        filter: &Filter,
+        settings: &SearchSettings,
+        matches: &mut Vec<FieldMatch>,
+    ) -> Option<f64> {
+        let contains = |text: Option<&str>, value: &str| text.is_some_and(|text| Self::normalize_label(text, settings).contains(value));
+
+        let matched = match filter {
+            Filter::Term(term) => {
+                let term: Vec<char> = term.chars().collect();
+                return Self::match_fields(fields, texts, matches, |text| Self::fuzzy_match(&term, text));
+            }
+            Filter::Phrase(phrase) => {
+                let phrase: Vec<char> = phrase.chars().collect();
+                return Self::match_fields(fields, texts, matches, |text| Self::substring_match(&phrase, text));
+            }
+            Filter::Type(value) => item.content_type().eq_ignore_ascii_case(value),
+            Filter::Tag(value) => item.tags().iter().any(|tag| Self::normalize_label(tag, settings) == *value),
+            Filter::App(value) => contains(item.source_app(), value),
+            Filter::Name(value) => contains(item.name(), value),
+            Filter::Note(value) => contains(item.note(), value),
+            Filter::Is(flag) => item.has_flag(flag),
+            Filter::Kind(kind) => item.kind() == kind,
+            Filter::After(cutoff) => item.timestamp() >= *cutoff,
+            Filter::Before(cutoff) => item.timestamp() < *cutoff,
+            Filter::On(date) => item.timestamp().with_timezone(&Local).date_naive() == *date,
+            Filter::Size(comparison, bytes) => comparison.matches(item.size() as u64, *bytes),
+            Filter::Not(inner) => {
+                // 否定した語はあいまい一致ではなく部分一致で判定する
+                let inner = match inner.as_ref() {
+                    Filter::Term(term) => &Filter::Phrase(term.clone()),
+                    other => other,
+                };
+                Self::evaluate_filter(item, fields, texts, inner, settings, &mut Vec::new()).is_none()
+            }
+        };
+
+        matched.then_some(0.0)
+    }
+
+    // いずれかのフィールドに一致すれば最良の重み付きスコアを返し、一致範囲を記録する
+    // 一致範囲は正規化前のテキストの文字位置に戻して記録する
+    fn match_fields<F>(fields: &[Field], texts: &[Normalized], matches: &mut Vec<FieldMatch>, matcher: F) -> Option<f64>
+    where
+        F: Fn(&[char]) -> Option<FuzzyMatch>,
+    {
+        let mut best_score: Option<f64> = None;
+
+        for (field, text) in fields.iter().zip(texts) {
+            let Some(mut fuzzy) = matcher(&text.chars) else {
+                continue;
+            };
+            for range in &mut fuzzy.ranges {
+                *range = text.original_range(*range);
+            }
+            let score = fuzzy.score * field.weight;
+            best_score = Some(best_score.map_or(score, |best| best.max(score)));
+
+            match matches.iter_mut().find(|m| m.field == field.name && m.index == field.index) {
+                Some(existing) => existing.ranges.extend(fuzzy.ranges),
+                None => matches.push(FieldMatch {
+                    field: field.name.to_string(),
+                    index: field.index,
+                    ranges: fuzzy.ranges,
+                }),
+            }
+        }
+
+        best_score
+    }
+
+    // 正規化済みテキストの部分一致（単語の先頭・テキスト先頭での一致を高く評価）
+    fn substring_match(query: &[char], text: &[char]) -> Option<FuzzyMatch> {
+        if query.is_empty() {
+            return None;
+        }
+        let start = text.windows(query.len()).position(|window| window == query)?;
+
+        let mut score = 1.0;
+        if Self::is_boundary(text, start) {
+            score += 0.5;
+        }
+        if start == 0 {
+            score += 0.25;
+        }
+        Some(FuzzyMatch {
+            score,
+            ranges: vec![[start, start + query.len()]],
+        })
+    }
+
+    // 連続一致を優先し、無ければ最も狭い範囲に収まる部分列一致を探す
+    fn fuzzy_match(query: &[char], text: &[char]) -> Option<FuzzyMatch> {
+        if let Some(substring) = Self::substring_match(query, text) {
+            return Some(substring);
+        }
+
+        if query.is_empty() {
+            return None;
+        }
+        let text = &text[..text.len().min(MAX_FUZZY_PREFIX_CHARS)];
+
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        for start in (0..text.len()).filter(|&pos| text[pos] == query[0]).take(MAX_FUZZY_STARTS) {
+            let Some(positions) = Self::subsequence_from(text, query, start) else {
+                break; // これ以降の開始位置でも一致しない
+            };
+            let span = positions[positions.len() - 1] - positions[0] + 1;
+            if best.as_ref().map_or(true, |(best_span, _)| span < *best_span) {
+                best = Some((span, positions));
+            }
+        }
+
+        let (span, positions) = best.filter(|(span, _)| *span <= query.len() * MAX_FUZZY_SPAN_RATIO)?;
+        // 一致した文字が疎らなほどスコアを下げる（連続一致より常に低い）
+        let compactness = query.len() as f64 / span as f64;
+        let boundary_hits = positions.iter().filter(|&&pos| Self::is_boundary(text, pos)).count();
+        let score = 0.6 * compactness + 0.2 * boundary_hits as f64 / query.len() as f64;
+
+        Some(FuzzyMatch {
+            score,
+            ranges: Self::merge_ranges(positions.into_iter().map(|pos| [pos, pos + 1]).collect()),
+        })
+    }
+
+    fn subsequence_from(text: &[char], query: &[char], start: usize) -> Option<Vec<usize>> {
+        let mut positions = Vec::with_capacity(query.len());
+        let mut query_chars = query.iter().peekable();
+
+        for (pos, c) in text.iter().enumerate().skip(start) {
+            match query_chars.peek() {
+                Some(&&expected) if expected == *c => {
+                    positions.push(pos);
+                    query_chars.next();
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        (positions.len() == query.len()).then_some(positions)
+    }
+
+    // 単語の先頭（テキスト先頭または英数字以外の直後）か
+    fn is_boundary(text: &[char], pos: usize) -> bool {
+        pos == 0 || !text[pos - 1].is_alphanumeric()
+    }
+
+    // 重なり・隣接する範囲をまとめる
+    fn merge_ranges(mut ranges: Vec<[usize; 2]>) -> Vec<[usize; 2]> {
+        ranges.sort_unstable();
+        let mut merged: Vec<[usize; 2]> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range[0] <= last[1] => last[1] = last[1].max(range[1]),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
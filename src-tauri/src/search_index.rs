@@ -0,0 +1,420 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use crate::models::{AppData, SearchSettings};
+use crate::normalize::TextNormalizer;
+use crate::query::{Filter, Query};
+use crate::search::{Searchable, MAX_FUZZY_PREFIX_CHARS};
+
+// 登録するN-gramの最大文字数（検索語はこの長さのN-gramに分けて照合する）
+const MAX_GRAM_CHARS: usize = 3;
+// N-gramを登録する最大文字数（正規化後の全フィールドの合計、超えるアイテムは常に候補にする）
+const MAX_INDEXED_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DocId {
+    kind: &'static str, // "history" | "bookmark" | "ip"
+    id: String,
+}
+
+// 登録済みのアイテム（内容のハッシュと、取り除くときに使う登録キー）
+struct Document {
+    fingerprint: u64,
+    grams: Vec<String>,
+    prefix_chars: Vec<char>,
+    oversized: bool,
+}
+
+// N-gram索引（N-gram → アイテム）
+// 検索と同じ正規化を適用したテキストの1〜3文字のN-gramをすべて登録し、部分一致の候補を漏れなく求める
+// 部分列一致の候補は、各フィールド先頭（部分列一致の対象範囲）に含まれる文字で求める
+// 候補は線形探索で一致するアイテムを必ず含む（絞り込めない場合は None を返す）
+#[derive(Default)]
+pub struct SearchIndex {
+    grams: HashMap<String, HashSet<DocId>>,
+    prefix_chars: HashMap<char, HashSet<DocId>>,
+    oversized: HashSet<DocId>,
+    documents: HashMap<DocId, Document>,
+    counts: HashMap<&'static str, usize>, // 種類ごとの登録件数
+    settings: SearchSettings,
+}
+
+impl SearchIndex {
+    // 起動時・読み込み時・正規化の設定変更時にすべてのアイテムから作り直す
+    pub fn rebuild(&mut self, data: &AppData) {
+        self.settings = data.settings.search.clone();
+        self.grams.clear();
+        self.prefix_chars.clear();
+        self.oversized.clear();
+        self.documents.clear();
+        self.counts.clear();
+
+        for item in &data.history {
+            self.upsert(item);
+        }
+        for bookmark in &data.bookmarks {
+            self.upsert(bookmark);
+        }
+        for ip_item in &data.recent_ips {
+            self.upsert(ip_item);
+        }
+        log::info!("検索インデックスを構築: {} 件, {} 語", self.documents.len(), self.grams.len());
+    }
+
+    // 登録時と正規化の設定が異なるか（作り直しが必要か）
+    pub fn is_stale(&self, settings: &SearchSettings) -> bool {
+        self.settings != *settings
+    }
+
+    // 検索の前に呼ぶ: 正規化の設定が変わっていれば作り直す
+    // 件数がデータと食い違う場合（更新漏れ）は差分だけを登録し直す。通常は件数の比較のみで終わる
+    pub fn refresh(&mut self, data: &AppData) {
+        if self.is_stale(&data.settings.search) {
+            self.rebuild(data);
+            return;
+        }
+        if self.count("history") != data.history.len()
+            || self.count("bookmark") != data.bookmarks.len()
+            || self.count("ip") != data.recent_ips.len()
+        {
+            log::warn!("検索インデックスの件数がデータと一致しないため差分を登録し直します");
+            self.reconcile(data);
+        }
+    }
+
+    // 削除されたアイテムを取り除き、未登録・変更されたアイテムを登録する（まとめて削除した後などに呼ぶ）
+    pub fn reconcile(&mut self, data: &AppData) {
+        let mut live: HashSet<DocId> = HashSet::new();
+        for item in &data.history {
+            live.insert(Self::doc_id(item));
+            self.upsert(item);
+        }
+        for bookmark in &data.bookmarks {
+            live.insert(Self::doc_id(bookmark));
+            self.upsert(bookmark);
+        }
+        for ip_item in &data.recent_ips {
+            live.insert(Self::doc_id(ip_item));
+            self.upsert(ip_item);
+        }
+
+        let stale: Vec<DocId> = self.documents.keys().filter(|doc| !live.contains(doc)).cloned().collect();
+        for doc in &stale {
+            self.remove_doc(doc);
+        }
+    }
+
+    // 削除されたアイテムだけを取り除く（件数が一致していれば何もしない）
+    pub fn prune(&mut self, data: &AppData) {
+        let ids = |kind: &'static str| -> Option<HashSet<&str>> {
+            let live: HashSet<&str> = match kind {
+                "history" => data.history.iter().map(|item| item.id.as_str()).collect(),
+                "bookmark" => data.bookmarks.iter().map(|bookmark| bookmark.id.as_str()).collect(),
+                _ => data.recent_ips.iter().map(|ip_item| ip_item.ip.as_str()).collect(),
+            };
+            (live.len() != self.count(kind)).then_some(live)
+        };
+        let live: Vec<(&'static str, HashSet<&str>)> = ["history", "bookmark", "ip"]
+            .into_iter()
+            .filter_map(|kind| ids(kind).map(|live| (kind, live)))
+            .collect();
+
+        for (kind, live) in live {
+            let stale: Vec<DocId> = self
+                .documents
+                .keys()
+                .filter(|doc| doc.kind == kind && !live.contains(doc.id.as_str()))
+                .cloned()
+                .collect();
+            for doc in &stale {
+                self.remove_doc(doc);
+            }
+        }
+    }
+
+    // アイテムを登録（内容が変わっていなければ何もしない）、登録し直した場合true
+    pub fn upsert<T: Searchable>(&mut self, item: &T) -> bool {
+        let doc = Self::doc_id(item);
+        let fields = item.search_fields();
+
+        let mut hasher = DefaultHasher::new();
+        for field in &fields {
+            field.name.hash(&mut hasher);
+            field.text.hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+        if self.documents.get(&doc).is_some_and(|existing| existing.fingerprint == fingerprint) {
+            return false;
+        }
+
+        self.remove_doc(&doc);
+
+        let texts: Vec<Vec<char>> = fields
+            .iter()
+            .map(|field| TextNormalizer::normalize(field.text, &self.settings).chars)
+            .collect();
+        let oversized = texts.iter().map(Vec::len).sum::<usize>() > MAX_INDEXED_CHARS;
+
+        let mut grams: HashSet<String> = HashSet::new();
+        let mut prefix_chars: HashSet<char> = HashSet::new();
+        for text in &texts {
+            prefix_chars.extend(text.iter().take(MAX_FUZZY_PREFIX_CHARS));
+            if !oversized {
+                for length in 1..=MAX_GRAM_CHARS {
+                    grams.extend(text.windows(length).map(|gram| gram.iter().collect::<String>()));
+                }
+            }
+        }
+
+        for gram in &grams {
+            self.grams.entry(gram.clone()).or_default().insert(doc.clone());
+        }
+        for c in &prefix_chars {
+            self.prefix_chars.entry(*c).or_default().insert(doc.clone());
+        }
+        if oversized {
+            self.oversized.insert(doc.clone());
+        }
+        *self.counts.entry(doc.kind).or_default() += 1;
+        self.documents.insert(doc, Document {
+            fingerprint,
+            grams: grams.into_iter().collect(),
+            prefix_chars: prefix_chars.into_iter().collect(),
+            oversized,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, kind: &'static str, id: &str) {
+        self.remove_doc(&DocId { kind, id: id.to_string() });
+    }
+
+    fn remove_doc(&mut self, doc: &DocId) {
+        let Some(document) = self.documents.remove(doc) else {
+            return;
+        };
+        for gram in document.grams {
+            if let Some(docs) = self.grams.get_mut(&gram) {
+                docs.remove(doc);
+                if docs.is_empty() {
+                    self.grams.remove(&gram);
+                }
+            }
+        }
+        for c in document.prefix_chars {
+            if let Some(docs) = self.prefix_chars.get_mut(&c) {
+                docs.remove(doc);
+                if docs.is_empty() {
+                    self.prefix_chars.remove(&c);
+                }
+            }
+        }
+        if document.oversized {
+            self.oversized.remove(doc);
+        }
+        if let Some(count) = self.counts.get_mut(doc.kind) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    fn count(&self, kind: &str) -> usize {
+        self.counts.get(kind).copied().unwrap_or(0)
+    }
+
+    fn doc_id<T: Searchable>(item: &T) -> DocId {
+        DocId {
+            kind: item.kind(),
+            id: item.id().to_string(),
+        }
+    }
+
+    // クエリの語・フレーズ（否定を除く）をすべて満たしうるアイテムのIDを返す
+    // 語・フレーズが無い場合や、正規化後に空になる語がある場合は絞り込まない（None）
+    pub fn candidates(&self, query: &Query, kind: &str) -> Option<HashSet<String>> {
+        let mut candidates: Option<HashSet<String>> = None;
+
+        for filter in &query.filters {
+            let docs = match filter {
+                Filter::Term(term) => {
+                    let term: Vec<char> = TextNormalizer::normalize(term, &self.settings).chars;
+                    if term.is_empty() {
+                        return None;
+                    }
+                    // 部分一致の候補と、部分列一致の候補を合わせたもの
+                    let mut docs = self.substring_candidates(&term, kind);
+                    docs.extend(self.subsequence_candidates(&term, kind));
+                    docs
+                }
+                Filter::Phrase(phrase) => {
+                    let phrase: Vec<char> = TextNormalizer::normalize(phrase, &self.settings).chars;
+                    if phrase.is_empty() {
+                        return None;
+                    }
+                    self.substring_candidates(&phrase, kind)
+                }
+                _ => continue,
+            };
+
+            candidates = Some(match candidates {
+                Some(current) => current.intersection(&docs).cloned().collect(),
+                None => docs,
+            });
+        }
+
+        candidates
+    }
+
+    // テキストを部分として含みうるアイテム（すべてのN-gramを含むものと、N-gramを登録していないもの）
+    fn substring_candidates(&self, text: &[char], kind: &str) -> HashSet<String> {
+        let length = text.len().min(MAX_GRAM_CHARS);
+        let grams: HashSet<String> = text.windows(length).map(|gram| gram.iter().collect()).collect();
+        let mut docs = self.intersect(grams.iter().map(|gram| self.grams.get(gram)), kind);
+        docs.extend(self.ids_of(&self.oversized, kind));
+        docs
+    }
+
+    // 部分列として一致しうるアイテム（いずれかのフィールド先頭に検索語のすべての文字を含むもの）
+    fn subsequence_candidates(&self, text: &[char], kind: &str) -> HashSet<String> {
+        let chars: HashSet<char> = text.iter().copied().collect();
+        self.intersect(chars.iter().map(|c| self.prefix_chars.get(c)), kind)
+    }
+
+    // すべての登録キーを含むアイテム（登録されていないキーがあれば空）
+    fn intersect<'a, I>(&self, postings: I, kind: &str) -> HashSet<String>
+    where
+        I: Iterator<Item = Option<&'a HashSet<DocId>>>,
+    {
+        let mut postings: Vec<&HashSet<DocId>> = match postings.collect::<Option<Vec<_>>>() {
+            Some(postings) if !postings.is_empty() => postings,
+            _ => return HashSet::new(),
+        };
+        postings.sort_by_key(|docs| docs.len());
+
+        let (smallest, rest) = postings.split_first().expect("postings is not empty");
+        smallest
+            .iter()
+            .filter(|doc| doc.kind == kind && rest.iter().all(|docs| docs.contains(*doc)))
+            .map(|doc| doc.id.clone())
+            .collect()
+    }
+
+    fn ids_of(&self, docs: &HashSet<DocId>, kind: &str) -> Vec<String> {
+        docs.iter().filter(|doc| doc.kind == kind).map(|doc| doc.id.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::models::{BookmarkItem, ClipboardItem, IpHistoryItem};
+    use crate::query::QueryParser;
+    use crate::search::Search;
+
+    fn sample_data() -> AppData {
+        let now = Utc::now();
+        let mut data = AppData::default();
+        for content in [
+            "ssh user@localhost",
+            "hostname -f",
+            "git commit -m \"Fix host lookup\"",
+            "ＨＯＳＴ名を確認する",
+            "東京都千代田区",
+            "ｶﾀｶﾅのテキスト",
+            "select * from users where id = 42",
+            "h-o-s-t spread out over a line",
+        ] {
+            data.history.push(ClipboardItem::captured_at(content.to_string(), "text".to_string(), now));
+        }
+        data.history.push(ClipboardItem::captured_at("x".repeat(MAX_INDEXED_CHARS) + " hidden host", "text".to_string(), now));
+        data.bookmarks.push(BookmarkItem::new(
+            "Deploy hosts".to_string(),
+            "ansible-playbook site.yml".to_string(),
+            "text".to_string(),
+            vec!["ops".to_string()],
+        ));
+        data.recent_ips.push(IpHistoryItem { ip: "192.168.10.20".to_string(), timestamp: now, count: 1 });
+        data
+    }
+
+    fn ids<T: Searchable>(items: &[T], query: &Query, candidates: Option<&HashSet<String>>, data: &AppData) -> Vec<String> {
+        let mut ids: Vec<String> = Search::rank(items, query, candidates, &data.settings.search)
+            .into_iter()
+            .map(|(item, _, _)| item.id().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn indexed_results_match_linear_scan() {
+        let mut data = sample_data();
+        let mut index = SearchIndex::default();
+        index.rebuild(&data);
+
+        let queries = [
+            "host", "HOST", "ost", "hstnm", "h", "\"user@local\"", "\"-f\"", "ホスト", "host 確認", "千代田",
+            "代", "カタカナ", "ｶﾀ", "users 42", "-host", "hosts", "ops", "168.10", "nomatch", "lookup host",
+        ];
+
+        for _ in 0..2 {
+            for text in queries {
+                let query = QueryParser::parse(text).unwrap();
+                let history = index.candidates(&query, "history");
+                let bookmarks = index.candidates(&query, "bookmark");
+                let ips = index.candidates(&query, "ip");
+
+                assert_eq!(ids(&data.history, &query, history.as_ref(), &data), ids(&data.history, &query, None, &data), "{}", text);
+                assert_eq!(ids(&data.bookmarks, &query, bookmarks.as_ref(), &data), ids(&data.bookmarks, &query, None, &data), "{}", text);
+                assert_eq!(ids(&data.recent_ips, &query, ips.as_ref(), &data), ids(&data.recent_ips, &query, None, &data), "{}", text);
+
+                // 件数表示用の集計も結果の件数と一致する
+                let settings = &data.settings.search;
+                assert_eq!(Search::count(&data.history, &query, history.as_ref(), settings), ids(&data.history, &query, None, &data).len(), "{}", text);
+            }
+
+            // 追加・編集・削除を反映した後も線形探索と一致する
+            let removed = data.history.remove(0);
+            index.remove("history", &removed.id);
+            data.history[0].content = "localhost:8080".to_string();
+            index.upsert(&data.history[0]);
+            data.history.push(ClipboardItem::captured_at("another host".to_string(), "text".to_string(), Utc::now()));
+            index.upsert(data.history.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn substring_matches_are_not_narrowed_away() {
+        let data = sample_data();
+        let mut index = SearchIndex::default();
+        index.rebuild(&data);
+
+        let query = QueryParser::parse("host").unwrap();
+        let candidates = index.candidates(&query, "history").unwrap();
+        let localhost = &data.history[0].id;
+        let hostname = &data.history[1].id;
+        assert!(candidates.contains(localhost));
+        assert!(candidates.contains(hostname));
+        // N-gramを登録していない大きなアイテムも候補に含める
+        assert!(candidates.contains(&data.history[8].id));
+    }
+
+    #[test]
+    fn prune_and_refresh_follow_data_changes() {
+        let mut data = sample_data();
+        let mut index = SearchIndex::default();
+        index.rebuild(&data);
+
+        data.history.truncate(2);
+        index.prune(&data);
+        assert_eq!(index.count("history"), 2);
+
+        data.bookmarks.push(BookmarkItem::new("new".to_string(), "host".to_string(), "text".to_string(), Vec::new()));
+        index.refresh(&data);
+        assert_eq!(index.count("bookmark"), 2);
+
+        data.settings.search.fold_case = !data.settings.search.fold_case;
+        index.refresh(&data);
+        assert!(!index.is_stale(&data.settings.search));
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use regex::Regex;
+
+// 機密情報の検出パターン（キャプチャ時に評価）
+static AWS_ACCESS_KEY: OnceLock<Regex> = OnceLock::new();
+static AWS_SECRET_KEY: OnceLock<Regex> = OnceLock::new();
+static GITHUB_TOKEN: OnceLock<Regex> = OnceLock::new();
+static PRIVATE_KEY: OnceLock<Regex> = OnceLock::new();
+static CARD_CANDIDATE: OnceLock<Regex> = OnceLock::new();
+
+// カードブランドごとの先頭番号の範囲（同じ桁数で比較）と有効な桁数
+const CARD_BRANDS: &[(u32, u32, &[usize])] = &[
+    (4, 4, &[13, 16, 19]),           // Visa
+    (51, 55, &[16]),                 // Mastercard
+    (2221, 2720, &[16]),             // Mastercard（2系列）
+    (34, 34, &[15]),                 // American Express
+    (37, 37, &[15]),                 // American Express
+    (6011, 6011, &[16, 19]),         // Discover
+    (644, 649, &[16, 19]),           // Discover
+    (65, 65, &[16, 19]),             // Discover
+    (3528, 3589, &[16, 17, 18, 19]), // JCB
+    (300, 305, &[14]),               // Diners Club
+    (36, 36, &[14]),                 // Diners Club
+    (38, 39, &[14]),                 // Diners Club
+    (62, 62, &[16, 17, 18, 19]),     // UnionPay
+];
+
+// 区切り文字を含む場合に認めるグループの桁数
+const CARD_GROUPINGS: &[&[usize]] = &[
+    &[4, 4, 4, 4],    // 16桁
+    &[4, 4, 4, 4, 3], // 19桁
+    &[4, 6, 5],       // American Express
+    &[4, 6, 4],       // Diners Club
+    &[4, 4, 5],       // 13桁
+];
+
+pub struct SensitiveDetector;
+
+impl SensitiveDetector {
+    // テキストを検査し、機密情報の種類を返す（該当なしはNone）
+    pub fn detect(text: &str) -> Option<&'static str> {
+        let aws_access_key = AWS_ACCESS_KEY
+            .get_or_init(|| Regex::new(r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b").unwrap());
+        if aws_access_key.is_match(text) {
+            return Some("aws_access_key");
+        }
+
+        let aws_secret_key = AWS_SECRET_KEY.get_or_init(|| {
+            Regex::new(r"(?i)aws_secret_access_key\s*[=:]\s*[A-Za-z0-9/+=]{40}").unwrap()
+        });
+        if aws_secret_key.is_match(text) {
+            return Some("aws_secret_key");
+        }
+
+        let github_token = GITHUB_TOKEN.get_or_init(|| {
+            Regex::new(r"\b(?:gh[pousr]_[A-Za-z0-9]{36,255}|github_pat_[A-Za-z0-9_]{22,255})\b").unwrap()
+        });
+        if github_token.is_match(text) {
+            return Some("github_token");
+        }
+
+        let private_key = PRIVATE_KEY
+            .get_or_init(|| Regex::new(r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----").unwrap());
+        if private_key.is_match(text) {
+            return Some("private_key");
+        }
+
+        if Self::contains_credit_card(text) {
+            return Some("credit_card");
+        }
+
+        None
+    }
+
+    // 13〜19桁の数字列（空白・ハイフン区切り可）のうち、発行者の番号体系に合いLuhnチェックを通るものを検出
+    // タイムスタンプや注文番号などの長い数字を誤検出しないよう、先頭の番号と桁数の組み合わせ・区切り方も確認する
+    fn contains_credit_card(text: &str) -> bool {
+        let candidate = CARD_CANDIDATE
+            .get_or_init(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+
+        candidate.find_iter(text).any(|m| {
+            let digits: Vec<u32> = m.as_str().chars().filter_map(|c| c.to_digit(10)).collect();
+            Self::has_card_grouping(m.as_str())
+                && Self::matches_card_brand(&digits)
+                && Self::passes_luhn(&digits)
+        })
+    }
+
+    // 区切りなし、または同じ区切り文字でカードの表記どおりに区切られているか
+    fn has_card_grouping(candidate: &str) -> bool {
+        let separators: HashSet<char> = candidate.chars().filter(|c| !c.is_ascii_digit()).collect();
+        if separators.is_empty() {
+            return true;
+        }
+        if separators.len() > 1 {
+            return false;
+        }
+
+        let groups: Vec<usize> = candidate.split([' ', '-']).map(str::len).collect();
+        CARD_GROUPINGS.contains(&groups.as_slice())
+    }
+
+    // 先頭の番号（IIN）と桁数の組み合わせが既知のカードブランドに一致するか
+    fn matches_card_brand(digits: &[u32]) -> bool {
+        CARD_BRANDS.iter().any(|(low, high, lengths)| {
+            let prefix_len = low.to_string().len();
+            let prefix = digits.iter().take(prefix_len).fold(0, |acc, d| acc * 10 + d);
+            (*low..=*high).contains(&prefix) && lengths.contains(&digits.len())
+        })
+    }
+
+    fn passes_luhn(digits: &[u32]) -> bool {
+        let sum: u32 = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &d)| {
+                if i % 2 == 1 {
+                    let doubled = d * 2;
+                    if doubled > 9 { doubled - 9 } else { doubled }
+                } else {
+                    d
+                }
+            })
+            .sum();
+
+        sum % 10 == 0
+    }
+
+    // パスワードマネージャーが付与する org.nspasteboard.ConcealedType を確認（macOS専用）
+    #[cfg(target_os = "macos")]
+    pub fn is_clipboard_concealed() -> bool {
+        use std::process::Command;
+
+        let script = r#"ObjC.import('AppKit');
+            var types = $.NSPasteboard.generalPasteboard.types;
+            types.containsObject('org.nspasteboard.ConcealedType') ? 'true' : 'false';"#;
+
+        match Command::new("osascript").arg("-l").arg("JavaScript").arg("-e").arg(script).output() {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).trim() == "true"
+            }
+            Ok(_) => false,
+            Err(e) => {
+                log::warn!("ConcealedType確認エラー: {}", e);
+                false
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn is_clipboard_concealed() -> bool {
+        false // non-macOSでは判定できない
+    }
+
+    // 一覧表示用のマスク文字列
+    pub fn mask(kind: &str) -> String {
+        let label = match kind {
+            "aws_access_key" => "AWSアクセスキー",
+            "aws_secret_key" => "AWSシークレットキー",
+            "github_token" => "GitHubトークン",
+            "private_key" => "秘密鍵",
+            "credit_card" => "クレジットカード番号",
+            "concealed" => "パスワード",
+            _ => "機密情報",
+        };
+        format!("•••••••• ({})", label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_card_numbers_with_known_prefixes_and_groupings() {
+        assert_eq!(SensitiveDetector::detect("4111111111111111"), Some("credit_card"));
+        assert_eq!(SensitiveDetector::detect("card: 4111 1111 1111 1111"), Some("credit_card"));
+        assert_eq!(SensitiveDetector::detect("5500-0000-0000-0004"), Some("credit_card"));
+        assert_eq!(SensitiveDetector::detect("3782 822463 10005"), Some("credit_card"));
+    }
+
+    #[test]
+    fn ignores_long_numbers_that_are_not_card_numbers() {
+        // Luhnチェックは通るが、先頭の番号や桁数がカードの番号体系に合わない
+        assert!(SensitiveDetector::passes_luhn(&[1, 7, 1, 8, 0, 0, 0, 0, 0, 0, 0, 0, 6]));
+        assert_eq!(SensitiveDetector::detect("created_at=1718000000006"), None);
+        assert!(SensitiveDetector::passes_luhn(&[1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 3, 4, 5, 6, 7, 0]));
+        assert_eq!(SensitiveDetector::detect("order 1234567812345670"), None);
+        assert_eq!(SensitiveDetector::detect("tracking 9400100000000000002"), None);
+
+        // 番号体系には合うが、カードの表記と異なる区切り方・区切り文字の混在
+        assert_eq!(SensitiveDetector::detect("4111-111-1111-11111"), None);
+        assert_eq!(SensitiveDetector::detect("4111 1111-1111 1111"), None);
+
+        // Amexの先頭番号でも桁数が合わない
+        assert_eq!(SensitiveDetector::detect("3400000000000000"), None);
+    }
+}
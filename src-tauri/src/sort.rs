@@ -0,0 +1,102 @@
+use std::cmp::Reverse;
+use chrono::{DateTime, Utc};
+use crate::frecency;
+use crate::models::SearchSettings;
+use crate::normalize::TextNormalizer;
+use crate::search::Searchable;
+
+// 一覧の並び順
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortMode {
+    Recent,       // 追加・更新が新しい順
+    Frequency,    // 利用回数の多い順（同数は最終利用が新しい順）
+    Frecency,     // 利用回数と最終利用からの経過時間を組み合わせたスコア順
+    Alphabetical, // 本文（履歴はタイトルがあればタイトル）の五十音・アルファベット順
+    Name,         // 名前の五十音・アルファベット順
+    Size,         // サイズの大きい順
+}
+
+impl SortMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "recent" => Ok(Self::Recent),
+            "frequency" => Ok(Self::Frequency),
+            "frecency" => Ok(Self::Frecency),
+            "alphabetical" => Ok(Self::Alphabetical),
+            "name" => Ok(Self::Name),
+            "size" => Ok(Self::Size),
+            _ => Err(format!("Unknown sort mode: {}", value)),
+        }
+    }
+}
+
+pub struct Sorter;
+
+impl Sorter {
+    // ピン留めを先頭に、指定の順序で並べる（text は五十音順で比較する文字列）
+    pub fn sort<T, F>(items: &mut [&T], mode: SortMode, now: DateTime<Utc>, text: F)
+    where
+        T: Searchable,
+        F: Fn(&T) -> &str,
+    {
+        match mode {
+            SortMode::Recent => items.sort_by_key(|item| (Reverse(item.has_flag("pinned")), Reverse(item.timestamp()))),
+            SortMode::Frequency => items.sort_by_key(|item| (Reverse(item.has_flag("pinned")), Reverse(item.usage()))),
+            SortMode::Frecency => items.sort_by(|a, b| {
+                let score = |item: &T| {
+                    let (count, last_used) = item.usage();
+                    frecency::score(count, last_used, now)
+                };
+                b.has_flag("pinned").cmp(&a.has_flag("pinned")).then(score(b).total_cmp(&score(a)))
+            }),
+            SortMode::Alphabetical => {
+                items.sort_by_cached_key(|item| (Reverse(item.has_flag("pinned")), Self::collation_key(text(*item))))
+            }
+            SortMode::Name => items.sort_by_cached_key(|item| {
+                (Reverse(item.has_flag("pinned")), Self::collation_key(item.name().unwrap_or_else(|| text(*item))))
+            }),
+            SortMode::Size => items.sort_by_key(|item| (Reverse(item.has_flag("pinned")), Reverse(item.size()))),
+        }
+    }
+
+    // 日本語の辞書順に近い比較キー
+    // 全角・半角、カタカナ・ひらがな、大文字・小文字を同一視し、まず濁点・小書きの違いを無視して比較する
+    // 漢字は読みが分からないため文字コード順（かなの後）
+    pub fn collation_key(text: &str) -> CollationKey {
+        let folded: Vec<char> = TextNormalizer::normalize(text.trim(), &SearchSettings::default()).chars;
+        let primary = folded.iter().map(|&c| Self::base_kana(c)).collect();
+        CollationKey {
+            primary,
+            secondary: folded,
+            original: text.to_string(),
+        }
+    }
+
+    // 濁点・半濁点付きのかなと小書きのかなを元の清音に寄せる（ひらがな）
+    fn base_kana(c: char) -> char {
+        let code = c as u32;
+        let base = match code {
+            0x3041..=0x304A if code % 2 == 1 => code + 1,                   // ぁ〜ぉ
+            0x304C..=0x3062 if code % 2 == 0 => code - 1,                   // が〜ぢ
+            0x3063 => 0x3064,                                                // っ
+            0x3065 | 0x3067 | 0x3069 => code - 1,                            // づ で ど
+            0x3070..=0x307D => code - (code - 0x306F) % 3,                   // ば・ぱ〜ぼ・ぽ
+            0x3083 | 0x3085 | 0x3087 => code + 1,                            // ゃ ゅ ょ
+            0x308E => 0x308F,                                                // ゎ
+            0x3094 => 0x3046,                                                // ゔ
+            0x3095 => 0x304B,                                                // ゕ
+            0x3096 => 0x3051,                                                // ゖ
+            0x309E => 0x309D,                                                // ゞ
+            _ => code,
+        };
+        char::from_u32(base).unwrap_or(c)
+    }
+}
+
+// 清音に寄せた比較 → 濁点・小書きを区別した比較 → 元の文字列の順で比較する（フィールド順に比較）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CollationKey {
+    primary: Vec<char>,
+    secondary: Vec<char>,
+    original: String,
+}
@@ -0,0 +1,79 @@
+use tauri::{AppHandle, Wry};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem};
+use crate::models::CaptureState;
+
+pub const TRAY_ID: &str = "main-tray";
+
+// システムトレイ管理クラス
+#[derive(Debug, Clone)]
+pub struct TrayManager {
+    app_handle: AppHandle,
+}
+
+impl TrayManager {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+
+    // キャプチャ状態に応じたトレイメニューを作成
+    pub fn build_menu(&self, state: &CaptureState) -> tauri::Result<Menu<Wry>> {
+        let app = &self.app_handle;
+
+        let pause_label = if state.paused { "監視を再開" } else { "監視を一時停止" };
+
+        let show_item = MenuItem::with_id(app, "show", "ウィンドウを表示", true, None::<&str>)?;
+        let hide_item = MenuItem::with_id(app, "hide", "ウィンドウを非表示", true, None::<&str>)?;
+        let pause_item = MenuItem::with_id(app, "toggle_pause", pause_label, true, None::<&str>)?;
+        let pause_timed_item = MenuItem::with_id(app, "pause_15", "15分間一時停止", !state.paused, None::<&str>)?;
+        let incognito_item = CheckMenuItem::with_id(app, "toggle_incognito", "シークレットモード", true, state.incognito, None::<&str>)?;
+        let clear_item = MenuItem::with_id(app, "clear", "履歴をクリア", true, None::<&str>)?;
+        let quit_item = MenuItem::with_id(app, "quit", "終了", true, None::<&str>)?;
+
+        Menu::with_items(app, &[
+            &show_item,
+            &hide_item,
+            &pause_item,
+            &pause_timed_item,
+            &incognito_item,
+            &clear_item,
+            &quit_item,
+        ])
+    }
+
+    pub fn tooltip(state: &CaptureState) -> String {
+        let mut tooltip = match (state.paused, state.paused_until) {
+            (true, Some(until)) => format!(
+                "一時停止中（{}まで）",
+                until.with_timezone(&chrono::Local).format("%H:%M")
+            ),
+            (true, None) => "一時停止中".to_string(),
+            (false, _) => "クリップボード監視中".to_string(),
+        };
+
+        if state.incognito {
+            tooltip.push_str(" / シークレットモード");
+        }
+
+        tooltip
+    }
+
+    // 状態変更をトレイのメニューとツールチップに反映
+    pub fn refresh(&self, state: &CaptureState) {
+        let Some(tray) = self.app_handle.tray_by_id(TRAY_ID) else {
+            return;
+        };
+
+        match self.build_menu(state) {
+            Ok(menu) => {
+                if let Err(e) = tray.set_menu(Some(menu)) {
+                    log::warn!("トレイメニュー更新エラー: {}", e);
+                }
+            }
+            Err(e) => log::warn!("トレイメニュー作成エラー: {}", e),
+        }
+
+        if let Err(e) = tray.set_tooltip(Some(Self::tooltip(state))) {
+            log::warn!("トレイツールチップ更新エラー: {}", e);
+        }
+    }
+}